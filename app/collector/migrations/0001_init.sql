@@ -0,0 +1,100 @@
+CREATE DATABASE IF NOT EXISTS {database};
+
+CREATE DATABASE IF NOT EXISTS {log_database};
+
+CREATE TABLE IF NOT EXISTS {database}.article
+(
+    uid                UUID,
+    gallery            LowCardinality(String),
+    id                 UInt64,
+    timestamp          DateTime64(6, 'UTC'),
+    created_at         DateTime64(6, 'UTC'),
+    author             String,
+    author_uid         Nullable(String),
+    author_ip          Nullable(String),
+    author_kind        LowCardinality(String),
+    subject            String,
+    content            String,
+    content_html       String,
+    content_markdown   String,
+    attach_origin_src  Array(String),
+    attach_copied_path Array(String),
+    views              UInt64,
+    upvotes            UInt64,
+    downvotes          UInt64,
+    comments           UInt64
+)
+ENGINE = MergeTree
+ORDER BY (gallery, id);
+
+CREATE TABLE IF NOT EXISTS {database}.article_missing
+(
+    uid       UUID,
+    gallery   LowCardinality(String),
+    id        UInt64,
+    timestamp DateTime64(6, 'UTC')
+)
+ENGINE = MergeTree
+ORDER BY (gallery, id);
+
+CREATE TABLE IF NOT EXISTS {database}.article_deleted
+(
+    uid       UUID,
+    gallery   LowCardinality(String),
+    id        UInt64,
+    timestamp DateTime64(6, 'UTC')
+)
+ENGINE = MergeTree
+ORDER BY (gallery, id);
+
+CREATE TABLE IF NOT EXISTS {database}.article_counter
+(
+    uid       UUID,
+    gallery   LowCardinality(String),
+    id        UInt64,
+    timestamp DateTime64(6, 'UTC'),
+    views     UInt64,
+    upvotes   UInt64,
+    downvotes UInt64,
+    comments  UInt64
+)
+ENGINE = MergeTree
+ORDER BY (gallery, id, timestamp);
+
+CREATE TABLE IF NOT EXISTS {database}.comment
+(
+    uid          UUID,
+    gallery      LowCardinality(String),
+    article_id   UInt64,
+    no           UInt64,
+    parent_no    Nullable(UInt64),
+    depth        UInt64,
+    timestamp    DateTime64(6, 'UTC'),
+    created_at   Nullable(DateTime64(6, 'UTC')),
+    author       String,
+    author_uid   Nullable(String),
+    author_ip    Nullable(String),
+    content      String,
+    content_html String,
+    deleted      Bool
+)
+ENGINE = MergeTree
+ORDER BY (gallery, article_id, no);
+
+CREATE TABLE IF NOT EXISTS {log_database}.logs
+(
+    timestamp    DateTime64(6, 'UTC'),
+    uuid         UUID,
+    level        LowCardinality(String),
+    extra_keys   Array(String),
+    extra_values Array(String),
+    causes       Nullable(String),
+    file         Nullable(String),
+    line         Nullable(UInt64),
+    module_path  Nullable(String),
+    target       Nullable(String),
+    message      String,
+    user_id      Nullable(UUID)
+)
+ENGINE = MergeTree
+ORDER BY timestamp;
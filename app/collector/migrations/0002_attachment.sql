@@ -0,0 +1,12 @@
+ALTER TABLE {database}.article ADD COLUMN IF NOT EXISTS attach_hash Array(String) AFTER attach_copied_path;
+
+CREATE TABLE IF NOT EXISTS {database}.attachment
+(
+    uid        UUID,
+    hash       String,
+    first_seen DateTime64(6, 'UTC'),
+    size       UInt64,
+    mime       LowCardinality(String)
+)
+ENGINE = ReplacingMergeTree
+ORDER BY hash;
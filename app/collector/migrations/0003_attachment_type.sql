@@ -0,0 +1 @@
+ALTER TABLE {database}.attachment ADD COLUMN IF NOT EXISTS known_type Bool DEFAULT true AFTER mime;
@@ -0,0 +1,2 @@
+ALTER TABLE {database}.article ADD COLUMN IF NOT EXISTS attach_error_src Array(String) AFTER attach_hash;
+ALTER TABLE {database}.article ADD COLUMN IF NOT EXISTS attach_error Array(String) AFTER attach_error_src;
@@ -0,0 +1 @@
+ALTER TABLE {database}.article_counter MODIFY COLUMN downvotes Nullable(UInt64);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS {database}.comment_deleted
+(
+    uid        UUID,
+    gallery    LowCardinality(String),
+    article_id UInt64,
+    no         UInt64,
+    timestamp  DateTime64(6, 'UTC')
+)
+ENGINE = MergeTree
+ORDER BY (gallery, article_id, no);
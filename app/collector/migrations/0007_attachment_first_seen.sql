@@ -0,0 +1,20 @@
+DROP TABLE IF EXISTS {database}.attachment_v2;
+
+CREATE TABLE {database}.attachment_v2
+(
+    uid        SimpleAggregateFunction(any, UUID),
+    hash       String,
+    first_seen SimpleAggregateFunction(min, DateTime64(6, 'UTC')),
+    size       SimpleAggregateFunction(any, UInt64),
+    mime       SimpleAggregateFunction(any, LowCardinality(String)),
+    known_type SimpleAggregateFunction(any, Bool)
+)
+ENGINE = AggregatingMergeTree
+ORDER BY hash;
+
+INSERT INTO {database}.attachment_v2 SELECT uid, hash, first_seen, size, mime, known_type FROM {database}.attachment;
+
+-- skip if: SELECT count() FROM system.tables WHERE database = '{database}' AND name = 'attachment' AND engine = 'AggregatingMergeTree'
+EXCHANGE TABLES {database}.attachment AND {database}.attachment_v2;
+
+DROP TABLE {database}.attachment_v2;
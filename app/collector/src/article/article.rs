@@ -1,29 +1,95 @@
-use anyhow::{bail};
 use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
-use reqwest::{Client, StatusCode};
+use reqwest::StatusCode;
 use scraper::{ElementRef, Selector};
+use serde::Serialize;
+use crate::article::block;
+use crate::article::body::render;
+use crate::article::comment::extract_e_s_n_o;
+use crate::article::count::{parse_count, Counter};
+use crate::article::error::ArticleError;
+use crate::article::gallery::Gallery;
+use crate::http::profile::{Fetch, ProfileExt};
+use crate::http::proxy::{self, ProxyId};
+use crate::http::retry::RetryExt;
+use crate::http::session::Session;
+use crate::article::time::parse_kst;
 
 static _DELETED_SELECTOR: Lazy<Selector> =
     Lazy::new(|| Selector::parse("#container").unwrap()); // 삭제여부
-static CREATED_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse("#container header .gall_date").unwrap()); // 작성일시
-static AUTHOR_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse("#container header .nickname em").unwrap()); // 작성자
-static SUBJECT_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse("#container header .gallview_head .title_subject").unwrap()); // 제목
-static CONTENT_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse("#container .writing_view_box .write_div").unwrap()); // 본문
 static IMG_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("img").unwrap()); // 본문
-#[derive(Debug)]
+
+struct ViewSelectors {
+    created: Selector, // 작성일시
+    author: Selector,  // 작성자
+    writer: Selector,  // 작성자 data-uid, data-ip
+    nikcon: Selector,  // 고정닉/반고정닉 아이콘
+    subject: Selector, // 제목
+    content: Selector, // 본문
+    views: Selector,     // 조회수
+    comments: Selector,  // 댓글수
+    upvotes: Selector,   // 추천수
+    downvotes: Selector, // 비추천수
+}
+
+impl ViewSelectors {
+    fn new() -> Self {
+        Self {
+            created: Selector::parse("#container header .gall_date").unwrap(),
+            author: Selector::parse("#container header .nickname em").unwrap(),
+            writer: Selector::parse("#container header .gall_writer").unwrap(),
+            nikcon: Selector::parse("#container header .gall_writer .writer_nikcon img").unwrap(),
+            subject: Selector::parse("#container header .gallview_head .title_subject").unwrap(),
+            content: Selector::parse("#container .writing_view_box .write_div").unwrap(),
+            views: Selector::parse("#container header .gall_count").unwrap(),
+            comments: Selector::parse("#container header .gall_comment").unwrap(),
+            upvotes: Selector::parse("#container .btn_recommend_box .up_num").unwrap(),
+            downvotes: Selector::parse("#container .btn_recommend_box .down_num").unwrap(),
+        }
+    }
+}
+
+// 마이너/미니 갤러리는 본문 영역 앞에 말머리, 갤러리 정보 영역이 추가될 뿐 구조는 같다
+static VIEW_SELECTORS: Lazy<ViewSelectors> = Lazy::new(ViewSelectors::new);
+
+#[derive(Debug, Serialize)]
 pub struct Article {
+    pub gallery: String,
     pub id: u64,
     pub timestamp: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
     pub author: String,
+    pub author_uid: Option<String>,
+    pub author_ip: Option<String>,
+    pub author_kind: AuthorKind,
     pub subject: String,
     pub content: String,
+    pub content_html: String,
+    pub content_markdown: String,
     pub url: String,
     pub attach_src: Vec<String>,
+    pub counter: Counter,
+    #[serde(skip)]
+    pub e_s_n_o: Option<String>,
+}
+
+// 유동(비로그인), 반고정닉, 고정닉
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorKind {
+    Anonymous,
+    HalfFixed,
+    Fixed,
+}
+
+impl AuthorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthorKind::Anonymous => "anonymous",
+            AuthorKind::HalfFixed => "half_fixed",
+            AuthorKind::Fixed => "fixed",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -33,6 +99,7 @@ pub struct Attach {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum ArticleResult {
     Article(Article),
     DeletedArticle,
@@ -40,6 +107,7 @@ pub enum ArticleResult {
 
 #[derive(Debug)]
 pub struct DeletedArticle {
+    pub gallery: String,
     pub id: u64,
     pub timestamp: DateTime<Utc>,
 }
@@ -51,45 +119,102 @@ impl From<Article> for ArticleResult {
 }
 
 #[tracing::instrument]
-pub async fn get_article(client: Client, id: u64) -> anyhow::Result<ArticleResult> {
-    let (code, html, url) = http_page(client, id).await?;
+pub async fn get_article(client: Session, gallery: &Gallery, id: u64) -> Result<ArticleResult, ArticleError> {
+    let (code, html, url) = http_page(client, gallery, id).await?;
 
     if code == StatusCode::NOT_FOUND {
         return Ok(ArticleResult::DeletedArticle);
     }
 
     let dom = scraper::Html::parse_document(&html);
+    let selectors = &*VIEW_SELECTORS;
 
-    let _created_el = match dom.select(&CREATED_SELECTOR).next() {
+    let created_el = match dom.select(&selectors.created).next() {
         Some(v) => v,
-        None => bail!("not found created_el id: {}", id),
+        None => return Err(ArticleError::LayoutChanged { selector: "created", id }),
     };
-    let author_el = match dom.select(&AUTHOR_SELECTOR).next() {
+    let created_at = match parse_created_at(created_el) {
         Some(v) => v,
-        None => bail!("not found author_el id: {}", id),
+        None => return Err(ArticleError::LayoutChanged { selector: "created", id }),
     };
-    let subject_el = match dom.select(&SUBJECT_SELECTOR).next() {
+    let author_el = match dom.select(&selectors.author).next() {
         Some(v) => v,
-        None => bail!("not found subject_el id: {}", id),
+        None => return Err(ArticleError::LayoutChanged { selector: "author", id }),
     };
-    let content_el = match dom.select(&CONTENT_SELECTOR).next() {
+    let writer_el = match dom.select(&selectors.writer).next() {
         Some(v) => v,
-        None => bail!("not found content_el id: {}", id),
+        None => return Err(ArticleError::LayoutChanged { selector: "writer", id }),
     };
+    let author_uid = non_empty_attr(writer_el, "data-uid");
+    let author_ip = non_empty_attr(writer_el, "data-ip");
+    let author_kind = match dom.select(&selectors.nikcon).next().and_then(|v| v.value().attr("src")) {
+        _ if author_uid.is_none() => AuthorKind::Anonymous,
+        Some(src) if is_fixed_nikcon(src) => AuthorKind::Fixed,
+        _ => AuthorKind::HalfFixed,
+    };
+    let subject_el = match dom.select(&selectors.subject).next() {
+        Some(v) => v,
+        None => return Err(ArticleError::LayoutChanged { selector: "subject", id }),
+    };
+    let content_el = match dom.select(&selectors.content).next() {
+        Some(v) => v,
+        None => return Err(ArticleError::LayoutChanged { selector: "content", id }),
+    };
+
+    let body = render(content_el);
 
     Ok(
         Article {
+            gallery: gallery.to_string(),
             id,
             timestamp: Utc::now(),
+            created_at,
             author: author_el.text().collect::<String>(),
+            author_uid,
+            author_ip,
+            author_kind,
             subject: subject_el.text().collect::<String>(),
             content: content_el.text().collect::<String>().replace("\n", "").replace("\t", ""),
+            content_html: body.html,
+            content_markdown: body.markdown,
             attach_src: collect_attach_src(content_el),
             url,
+            e_s_n_o: extract_e_s_n_o(&dom),
+            counter: Counter {
+                views: parse_count(dom.select(&selectors.views).next()),
+                upvotes: parse_count(dom.select(&selectors.upvotes).next()),
+                downvotes: parse_count(dom.select(&selectors.downvotes).next()),
+                comments: parse_count(dom.select(&selectors.comments).next()),
+            },
         }.into()
     )
 }
 
+// title 속성에 초 단위까지 있고, 없으면 표시된 텍스트를 쓴다
+fn parse_created_at(element: ElementRef<'_>) -> Option<DateTime<Utc>> {
+    element
+        .value()
+        .attr("title")
+        .and_then(parse_kst)
+        .or_else(|| parse_kst(&element.text().collect::<String>()))
+}
+
+// 고정닉 아이콘은 fix_nik, 매니저/부매니저는 fix_managernik, fix_sub_managernik 처럼 파일명이 fix_ 로 시작한다
+fn is_fixed_nikcon(src: &str) -> bool {
+    let path = src.split(['?', '#']).next().unwrap_or_default();
+
+    path.rsplit('/').next().is_some_and(|name| name.starts_with("fix_"))
+}
+
+fn non_empty_attr(element: ElementRef<'_>, name: &str) -> Option<String> {
+    element
+        .value()
+        .attr(name)
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
 fn collect_attach_src(element: ElementRef<'_>) -> Vec<String> {
     let mut result = Vec::<String>::new();
 
@@ -103,21 +228,43 @@ fn collect_attach_src(element: ElementRef<'_>) -> Vec<String> {
 }
 
 #[tracing::instrument]
-async fn http_page(client: Client, page_number: u64) -> anyhow::Result<(StatusCode, String, String)> {
-    let url = format!(
-        "https://gall.dcinside.com/board/view/?id=baseball_new13&no={page_number}&page=1"
-    );
+async fn http_page(client: Session, gallery: &Gallery, page_number: u64) -> Result<(StatusCode, String, String), ArticleError> {
+    let url = gallery.view_url(page_number);
 
     let response = client
         .get(url.as_str())
-        .send()
+        .fetch(Fetch::Document, Some(&gallery.list_url(1)))
+        .send_with_retry()
         .await?;
 
     let status = response.status();
+    let proxy = ProxyId::of(&response);
+
+    // 성인 인증이 필요한 글은 인증 안내 페이지로 보내진다
+    if response.url().path().contains("/error/adult") {
+        return Err(ArticleError::AdultGate);
+    }
+
+    match status {
+        StatusCode::FORBIDDEN => {
+            proxy::blocked(proxy, status.as_str());
+            return Err(ArticleError::Blocked);
+        }
+        // send_with_retry 에서 이미 쉬게 했다
+        StatusCode::TOO_MANY_REQUESTS => return Err(ArticleError::Blocked),
+        s if !s.is_success() && s != StatusCode::NOT_FOUND => return Err(ArticleError::HttpStatus(s)),
+        _ => {}
+    }
+
     let response_body = response.text().await?;
 
-    if response_body.len() == 0 {
-        bail!("empty response body");
+    if response_body.is_empty() {
+        return Err(ArticleError::EmptyBody);
+    }
+
+    if let Some(reason) = block::detect(&response_body) {
+        proxy::blocked(proxy, reason);
+        return Err(ArticleError::Blocked);
     }
 
     Ok((status, response_body, url))
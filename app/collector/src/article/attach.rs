@@ -1,81 +1,194 @@
+use std::path::{Path, PathBuf};
 use anyhow::Context;
 use once_cell::sync::Lazy;
-use reqwest::Client;
+use sha2::{Digest, Sha256};
+use reqwest::Response;
 use tokio::io::AsyncWriteExt;
 use tracing::instrument;
 use uuid::Uuid;
+use crate::article::mime;
+use crate::config;
+use crate::http::profile::{Fetch, ProfileExt};
+use crate::http::retry::RetryExt;
+use crate::http::session::Session;
 
-static IMAGE_DIR: Lazy<std::path::PathBuf> = Lazy::new(|| {
-    let dir = std::env::var("IMAGE_DIR")
-        .map(std::path::PathBuf::from)
-        .unwrap_or_else(|_| std::path::PathBuf::from("./data/attach"));
+static IMAGE_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let dir = config::get().image_dir.clone();
 
     std::fs::create_dir_all(&dir).expect("failed to create IMAGE_DIR");
+    sweep_temp(&dir);
 
     dir
 });
 
+// 이전 실행이 죽으면서 남긴 임시 파일을 지운다
+fn sweep_temp(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return; };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') && name.ends_with(".tmp") {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+// 옮기기 전에 실패하거나 작업이 취소되어 drop 되면 임시 파일을 지운다
+struct TempFile {
+    path: PathBuf,
+    placed: bool,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.placed {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
 pub struct ArticleFile {
     pub origin_src: String,
-    pub copied_path: std::path::PathBuf,
+    pub copied_path: PathBuf,
+    pub hash: String, // 내용의 SHA-256
+    pub size: u64,
+    pub mime: String,
+    pub known_type: bool, // 시그니처로 형식을 판별하지 못했으면 false
 }
 
-pub async fn save_files(client: Client, attach_src: Vec<String>, referer: &str) -> anyhow::Result<Vec<ArticleFile>> {
-    let mut attach = Vec::<ArticleFile>::new();
+// 받지 못한 첨부. 게시글은 그대로 저장하고 원인만 남긴다
+pub struct AttachError {
+    pub origin_src: String,
+    pub reason: String,
+}
+
+#[derive(Default)]
+pub struct SavedAttach {
+    pub files: Vec<ArticleFile>,
+    pub errors: Vec<AttachError>,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum SaveError {
+    #[error("download failed: {0}")]
+    Download(#[from] reqwest::Error),
+    #[error("too large, over {0} bytes")]
+    TooLarge(u64),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// 다운로드 실패와 크기 초과는 첨부 오류로 남기고, 디스크 쓰기 실패만 게시글 전체를 실패로 본다
+pub async fn save_files(client: Session, attach_src: Vec<String>, referer: &str) -> anyhow::Result<SavedAttach> {
+    let mut attach = SavedAttach::default();
     for src in attach_src {
-        let path = save_file(client.clone(), &src, &referer)
-            .await
-            .context(format!("failed to save attach file {}", src))?;
-
-        attach.push(ArticleFile {
-            origin_src: src.to_string(),
-            copied_path: path,
-        });
+        match save_file(client.clone(), &src, referer).await {
+            Ok(file) => attach.files.push(file),
+            Err(SaveError::Io(e)) => {
+                return Err(e).context(format!("failed to save attach file {}", src));
+            }
+            Err(e) => {
+                tracing::warn!(src, error = %e, "attach file skipped");
+                attach.errors.push(AttachError { origin_src: src, reason: e.to_string() });
+            }
+        }
     }
 
     Ok(attach)
 }
 
+// 같은 내용은 한 번만 저장한다. 경로는 IMAGE_DIR/해시 앞 2자/다음 2자/해시.확장자
 #[instrument]
 async fn save_file(
-    client: Client,
+    client: Session,
     src: &str,
     referer: &str,
-) -> anyhow::Result<std::path::PathBuf> {
+) -> Result<ArticleFile, SaveError> {
     let response = client
         .get(src)
-        .header(reqwest::header::REFERER, referer)
-        .send()
-        .await
-        .context(format!("failed to download attach {}", src))?
+        .fetch(Fetch::Image, Some(referer))
+        .send_with_retry()
+        .await?
         .error_for_status()?;
 
-    let ext = extract_ext_from_cd(response.headers());
-    let bytes = response.bytes().await?;
+    let max_bytes = config::get().attach_max_bytes;
+    if response.content_length().is_some_and(|v| v > max_bytes) {
+        return Err(SaveError::TooLarge(max_bytes));
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    // 해시를 알기 전이므로 IMAGE_DIR 바로 아래 임시 파일에 받는다. 같은 파일시스템이라 rename 이 원자적이다
+    let mut temp = TempFile { path: IMAGE_DIR.join(format!(".{}.tmp", Uuid::now_v7())), placed: false };
+    let (hash, size, head) = download(response, &temp.path, max_bytes).await?;
 
-    let filename = format!("{}.{}", Uuid::now_v7(), ext);
-    let path = IMAGE_DIR.join(filename);
+    // Content-Disposition 의 파일명이나 Content-Type 은 실제 형식과 다른 경우가 많다
+    let file_type = mime::sniff(&head);
+    if file_type.is_none() {
+        tracing::warn!(src, content_type, "unknown attach file type");
+    }
+    let known_type = file_type.is_some();
+    let file_type = file_type.unwrap_or(mime::UNKNOWN);
 
-    let mut file = tokio::fs::File::create(&path).await?;
-    file.write_all(&bytes).await?;
+    let dir = IMAGE_DIR.join(&hash[0..2]).join(&hash[2..4]);
+    let path = dir.join(format!("{}.{}", hash, file_type.ext));
 
-    Ok(path)
+    place(&mut temp, &dir, &path).await?;
+
+    Ok(ArticleFile {
+        origin_src: src.to_string(),
+        copied_path: path,
+        hash,
+        size,
+        mime: file_type.mime.to_string(),
+        known_type,
+    })
 }
 
-fn extract_ext_from_cd(headers: &reqwest::header::HeaderMap) -> String {
-    headers
-        .get(reqwest::header::CONTENT_DISPOSITION)
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| {
-            v.split(';')
-                .find(|s| s.trim_start().starts_with("filename="))
-        })
-        .and_then(|s| {
-            let filename = s.trim().trim_start_matches("filename=").trim_matches('"');
-            std::path::Path::new(filename)
-                .extension()
-                .and_then(|e| e.to_str())
-        })
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "jpg".to_string())
-}
\ No newline at end of file
+// 본문을 조각 단위로 임시 파일에 쓰면서 해시를 계산한다. (해시, 크기, 형식 판별용 앞부분)
+async fn download(mut response: Response, temp: &Path, max_bytes: u64) -> Result<(String, u64, Vec<u8>), SaveError> {
+    let mut file = tokio::fs::File::create(temp).await?;
+    let mut hasher = Sha256::new();
+    let mut head = Vec::<u8>::with_capacity(mime::SNIFF_LEN);
+    let mut size = 0u64;
+
+    while let Some(chunk) = response.chunk().await? {
+        size += chunk.len() as u64;
+        // Content-Length 가 없거나 실제와 다른 경우
+        if size > max_bytes {
+            return Err(SaveError::TooLarge(max_bytes));
+        }
+
+        if head.len() < mime::SNIFF_LEN {
+            let take = (mime::SNIFF_LEN - head.len()).min(chunk.len());
+            head.extend_from_slice(&chunk[..take]);
+        }
+
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+
+    file.sync_all().await?;
+
+    Ok((format!("{:x}", hasher.finalize()), size, head))
+}
+
+// 이미 같은 내용이 있으면 옮기지 않는다. 임시 파일은 guard 가 지운다
+async fn place(temp: &mut TempFile, dir: &Path, path: &Path) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+
+    if tokio::fs::try_exists(path).await? {
+        return Ok(());
+    }
+
+    tokio::fs::rename(&temp.path, path).await?;
+    temp.placed = true;
+
+    Ok(())
+}
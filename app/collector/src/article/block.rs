@@ -0,0 +1,24 @@
+// 디시인사이드가 요청을 막을 때 200 으로 돌려주는 안내/캡차 페이지
+static MARKERS: &[(&str, &str)] = &[
+    ("g-recaptcha", "captcha"),
+    ("kcaptcha", "captcha"),
+    ("자동입력 방지", "captcha"),
+    ("잘못된 접근", "invalid_access"),
+    ("정상적인 접근이 아닙니다", "invalid_access"),
+    ("접근이 제한", "access_restricted"),
+];
+
+// 본문/목록 마크업이 있는 정상 페이지는 글 내용에 같은 문구가 있어도 차단으로 보지 않는다
+static NORMAL_MARKERS: &[&str] = &["writing_view_box", "gall_list"];
+
+// 차단 페이지라면 그 종류를 돌려준다
+pub fn detect(html: &str) -> Option<&'static str> {
+    if NORMAL_MARKERS.iter().any(|marker| html.contains(marker)) {
+        return None;
+    }
+
+    MARKERS
+        .iter()
+        .find(|(marker, _)| html.contains(marker))
+        .map(|(_, reason)| *reason)
+}
@@ -0,0 +1,211 @@
+use scraper::{ElementRef, Node};
+
+// 본문을 허용된 태그만 남긴 HTML 과 마크다운으로 옮긴다. 링크와 이미지는 본문 속 위치 그대로 남긴다
+#[derive(Debug, Default)]
+pub struct Body {
+    pub html: String,
+    pub markdown: String,
+    paragraph: bool, // <p> 를 연 채로 안쪽을 그리는 중이면 true
+}
+
+pub fn render(element: ElementRef<'_>) -> Body {
+    let mut body = Body::default();
+    render_children(element, &mut body);
+
+    body.html = body.html.trim().to_string();
+    body.markdown = collapse_blank_lines(&body.markdown);
+
+    body
+}
+
+fn render_children(element: ElementRef<'_>, body: &mut Body) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                let text = text.replace(['\n', '\t'], "");
+                body.html.push_str(&escape(&text));
+                body.markdown.push_str(&escape_markdown(&text));
+            }
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    render_element(child, body);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render_element(element: ElementRef<'_>, body: &mut Body) {
+    let value = element.value();
+
+    match value.name() {
+        "script" | "style" | "noscript" => {}
+        "br" => {
+            body.html.push_str("<br>");
+            body.markdown.push('\n');
+        }
+        // 문단 안의 p, div 는 <p> 를 겹치지 않고 줄바꿈으로 나눈다
+        "p" | "div" if body.paragraph => {
+            render_children(element, body);
+            body.html.push_str("<br>");
+            body.markdown.push('\n');
+        }
+        "p" | "div" => {
+            body.html.push_str("<p>");
+            body.paragraph = true;
+            render_children(element, body);
+            body.paragraph = false;
+            body.html.push_str("</p>");
+            body.markdown.push('\n');
+        }
+        // 허용하지 않는 주소의 링크는 글자만 남긴다
+        "a" => match value.attr("href").and_then(safe_url) {
+            Some(href) => {
+                body.html.push_str(&format!("<a href=\"{}\">", escape(&href)));
+                body.markdown.push('[');
+                render_children(element, body);
+                body.html.push_str("</a>");
+                body.markdown.push_str(&format!("]({})", markdown_url(&href)));
+            }
+            None => render_children(element, body),
+        },
+        "img" => {
+            if let Some(src) = value.attr("src").and_then(safe_url) {
+                body.html.push_str(&format!("<img src=\"{}\">", escape(&src)));
+                body.markdown.push_str(&format!("![]({})", markdown_url(&src)));
+            }
+        }
+        "iframe" | "video" | "embed" | "source" => {
+            match value.attr("src").and_then(safe_url) {
+                Some(src) => {
+                    body.html.push_str(&format!("<{0} src=\"{1}\"></{0}>", value.name(), escape(&src)));
+                    body.markdown.push_str(&format!("\n[{}]({})\n", value.name(), markdown_url(&src)));
+                }
+                None => render_children(element, body),
+            }
+        }
+        "b" | "strong" => wrap(element, body, "strong", "**"),
+        "i" | "em" => wrap(element, body, "em", "*"),
+        _ => render_children(element, body),
+    }
+}
+
+fn wrap(element: ElementRef<'_>, body: &mut Body, tag: &str, mark: &str) {
+    body.html.push_str(&format!("<{}>", tag));
+    body.markdown.push_str(mark);
+    render_children(element, body);
+    body.html.push_str(&format!("</{}>", tag));
+    body.markdown.push_str(mark);
+}
+
+// http, https 주소만 남긴다. // 로 시작하면 https 로 본다. javascript:, data:, 상대 주소는 None
+fn safe_url(v: &str) -> Option<String> {
+    let v = v.trim();
+
+    if let Some(rest) = v.strip_prefix("//") {
+        return Some(format!("https://{}", rest));
+    }
+
+    let (scheme, _) = v.split_once("://")?;
+    if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") {
+        Some(v.to_string())
+    } else {
+        None
+    }
+}
+
+fn escape(v: &str) -> String {
+    v.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// 본문 글자가 강조나 링크 문법으로 읽히지 않게 한다
+fn escape_markdown(v: &str) -> String {
+    let mut result = String::with_capacity(v.len());
+
+    for c in v.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+
+    result
+}
+
+// 공백이나 괄호가 있는 주소는 <...> 로 감싼다
+fn markdown_url(v: &str) -> String {
+    if v.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("<{}>", v.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        v.to_string()
+    }
+}
+
+fn collapse_blank_lines(v: &str) -> String {
+    let mut result = Vec::<&str>::new();
+
+    for line in v.lines().map(|l| l.trim_end()) {
+        if line.is_empty() && result.last().is_some_and(|l| l.is_empty()) {
+            continue;
+        }
+
+        result.push(line);
+    }
+
+    result.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::{Html, Selector};
+    use super::*;
+
+    fn render_html(html: &str) -> Body {
+        let dom = Html::parse_fragment(&format!("<div id=\"root\">{}</div>", html));
+        let root = dom.select(&Selector::parse("#root").unwrap()).next().unwrap();
+
+        render(root)
+    }
+
+    #[test]
+    fn nested_div_does_not_nest_paragraph() {
+        let body = render_html("<div><div>a</div><div>b</div></div><p>c</p>");
+
+        assert_eq!(body.html, "<p>a<br>b<br></p><p>c</p>");
+        assert_eq!(body.markdown, "a\nb\n\nc");
+    }
+
+    #[test]
+    fn escape_text() {
+        let body = render_html("<p>1 < 2 & *a_b* [c]</p>");
+
+        assert_eq!(body.html, "<p>1 &lt; 2 &amp; *a_b* [c]</p>");
+        assert_eq!(body.markdown, "1 < 2 & \\*a\\_b\\* \\[c\\]");
+    }
+
+    #[test]
+    fn link_and_image_stay_in_place() {
+        let body = render_html("<p>x <a href=\"https://a.com/b\">link</a> y <img src=\"//i.com/c d.png\"> z</p>");
+
+        assert_eq!(body.html, "<p>x <a href=\"https://a.com/b\">link</a> y <img src=\"https://i.com/c d.png\"> z</p>");
+        assert_eq!(body.markdown, "x [link](https://a.com/b) y ![](<https://i.com/c d.png>) z");
+    }
+
+    #[test]
+    fn reject_unsafe_scheme() {
+        let body = render_html(concat!(
+            "<p><a href=\"javascript:alert(1)\">a</a>",
+            "<a href=\" JavaScript:alert(1)\">b</a>",
+            "<img src=\"data:image/png;base64,AAAA\">",
+            "<iframe src=\"javascript:alert(1)\"></iframe>",
+            "<a href=\"/relative\">c</a></p>",
+        ));
+
+        assert_eq!(body.html, "<p>abc</p>");
+        assert_eq!(body.markdown, "abc");
+    }
+}
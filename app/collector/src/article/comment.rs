@@ -0,0 +1,195 @@
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use crate::article::gallery::Gallery;
+use crate::http::profile::{Fetch, ProfileExt};
+use crate::http::retry::RetryExt;
+use crate::http::session::Session;
+use crate::article::time::parse_kst_without_year;
+
+static E_S_N_O_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("input#e_s_n_o").unwrap()); // 댓글 API 토큰
+
+// 광고 댓글
+static COMMENT_BOY: &str = "COMMENT_BOY";
+const MAX_COMMENT_PAGE: u32 = 100;
+
+#[derive(Debug)]
+pub struct Comment {
+    pub no: u64,
+    pub parent_no: Option<u64>, // 답글이면 원 댓글 번호
+    pub depth: u64,
+    pub author: String,
+    pub author_uid: Option<String>,
+    pub author_ip: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub content: String,
+    pub content_html: String,
+    pub deleted: bool,
+}
+
+// complete 는 댓글 API 가 알려 준 전체 수(total_cnt)까지 모두 읽었으면 true
+#[derive(Debug)]
+pub struct Comments {
+    pub comments: Vec<Comment>,
+    pub complete: bool,
+}
+
+// 댓글 API 는 숫자를 문자열로 주기도 한다
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Number {
+    Int(u64),
+    Str(String),
+}
+
+impl Number {
+    fn value(&self) -> Option<u64> {
+        match self {
+            Number::Int(v) => Some(*v),
+            Number::Str(v) => v.trim().parse::<u64>().ok(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CommentResponse {
+    total_cnt: Option<Number>,
+    comments: Option<Vec<RawComment>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawComment {
+    no: Option<Number>,
+    c_no: Option<Number>,
+    depth: Option<Number>,
+    #[serde(default)]
+    user_id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    ip: String,
+    #[serde(default)]
+    reg_date: String,
+    #[serde(default)]
+    memo: String,
+    #[serde(default)]
+    del_yn: String,
+    #[serde(default)]
+    nicktype: String,
+}
+
+impl RawComment {
+    fn into_comment(self) -> Option<Comment> {
+        if self.nicktype == COMMENT_BOY {
+            return None;
+        }
+
+        let no = self.no.as_ref().and_then(Number::value)?;
+        let content = Html::parse_fragment(&self.memo)
+            .root_element()
+            .text()
+            .collect::<String>();
+
+        Some(Comment {
+            no,
+            parent_no: self.c_no.as_ref().and_then(Number::value).filter(|v| *v != 0),
+            depth: self.depth.as_ref().and_then(Number::value).unwrap_or(0),
+            author: self.name,
+            author_uid: Some(self.user_id).filter(|v| !v.is_empty()),
+            author_ip: Some(self.ip).filter(|v| !v.is_empty()),
+            created_at: parse_kst_without_year(&self.reg_date),
+            content,
+            content_html: self.memo,
+            deleted: self.del_yn == "Y",
+        })
+    }
+}
+
+pub fn extract_e_s_n_o(dom: &Html) -> Option<String> {
+    dom.select(&E_S_N_O_SELECTOR)
+        .next()
+        .and_then(|v| v.value().attr("value"))
+        .map(|v| v.to_string())
+        .filter(|v| !v.is_empty())
+}
+
+// 답글을 포함한 모든 댓글을 페이지를 넘기며 수집한다. MAX_COMMENT_PAGE 에서 멈추면 일부만 돌려준다
+#[tracing::instrument(skip(client, e_s_n_o))]
+pub async fn collect_comments(
+    client: Session,
+    gallery: &Gallery,
+    article_id: u64,
+    e_s_n_o: &str,
+) -> anyhow::Result<Comments> {
+    let mut result = Vec::<Comment>::new();
+    let mut fetched = 0;
+    let mut complete = false;
+
+    for page in 1..=MAX_COMMENT_PAGE {
+        let response = http_comment_page(client.clone(), gallery, article_id, e_s_n_o, page)
+            .await
+            .context(format!("fail to collect comment page {}", page))?;
+
+        let total = response.total_cnt.as_ref().and_then(Number::value).unwrap_or(0);
+        let comments = response.comments.unwrap_or_default();
+
+        if comments.is_empty() {
+            complete = fetched >= total;
+            break;
+        }
+
+        fetched += comments.len() as u64;
+        result.extend(comments.into_iter().filter_map(RawComment::into_comment));
+
+        if fetched >= total {
+            complete = true;
+            break;
+        }
+    }
+
+    Ok(Comments { comments: result, complete })
+}
+
+#[tracing::instrument(skip(client, e_s_n_o))]
+async fn http_comment_page(
+    client: Session,
+    gallery: &Gallery,
+    article_id: u64,
+    e_s_n_o: &str,
+    page: u32,
+) -> anyhow::Result<CommentResponse> {
+    let url = gallery.comment_url();
+    let referer = gallery.view_url(article_id);
+    let article_id = article_id.to_string();
+    let page = page.to_string();
+
+    let response = client
+        .post(url.as_str())
+        .fetch(Fetch::Xhr, Some(&referer))
+        .form(&[
+            ("id", gallery.id.as_str()),
+            ("no", article_id.as_str()),
+            ("cmt_id", gallery.id.as_str()),
+            ("cmt_no", article_id.as_str()),
+            ("e_s_n_o", e_s_n_o),
+            ("comment_page", page.as_str()),
+            ("sort", ""),
+            ("_GALLTYPE_", gallery.gall_type()),
+        ])
+        .send_with_retry()
+        .await
+        .context(format!("fail to request {}", url))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        bail!("comment response status {}", status);
+    }
+
+    let res = response.json::<CommentResponse>().await
+        .context(format!("fail to read comment response from {}", url))?;
+
+    Ok(res)
+}
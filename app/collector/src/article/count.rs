@@ -0,0 +1,20 @@
+use scraper::ElementRef;
+use serde::Serialize;
+
+// 조회수, 추천수, 비추천수, 댓글수
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Counter {
+    pub views: u64,
+    pub upvotes: u64,
+    pub downvotes: u64,
+    pub comments: u64,
+}
+
+// "조회 1,234", "[12]" 처럼 숫자 외 문자가 섞인 카운터에서 숫자만 읽는다
+pub fn parse_count(element: Option<ElementRef<'_>>) -> u64 {
+    element
+        .map(|v| v.text().collect::<String>())
+        .map(|v| v.chars().filter(|c| c.is_ascii_digit()).collect::<String>())
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0)
+}
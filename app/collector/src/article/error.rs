@@ -0,0 +1,29 @@
+use reqwest::StatusCode;
+
+// 수집 실패 원인. 원인별로 재시도, 알림 정책을 다르게 가져간다
+#[derive(Debug, thiserror::Error)]
+pub enum ArticleError {
+    #[error("network error: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("unexpected http status {0}")]
+    HttpStatus(StatusCode),
+    #[error("layout changed, not found {selector} id: {id}")]
+    LayoutChanged { selector: &'static str, id: u64 },
+    #[error("blocked by dcinside")]
+    Blocked,
+    #[error("adult verification required")]
+    AdultGate,
+    #[error("empty response body")]
+    EmptyBody,
+}
+
+impl ArticleError {
+    // 같은 요청을 다음 주기에 다시 시도할 만한지
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ArticleError::Network(_) | ArticleError::EmptyBody | ArticleError::Blocked => true,
+            ArticleError::HttpStatus(status) => status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS,
+            ArticleError::LayoutChanged { .. } | ArticleError::AdultGate => false,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::bail;
+use crate::config;
+
+// 갤러리 종류별로 URL 경로와 마크업이 다르다
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GalleryKind {
+    Major, // /board/
+    Minor, // /mgallery/board/
+    Mini,  // /mini/board/
+}
+
+impl GalleryKind {
+    fn path(&self) -> &'static str {
+        match self {
+            GalleryKind::Major => "/board",
+            GalleryKind::Minor => "/mgallery/board",
+            GalleryKind::Mini => "/mini/board",
+        }
+    }
+
+    // 댓글 API 의 _GALLTYPE_ 값
+    fn gall_type(&self) -> &'static str {
+        match self {
+            GalleryKind::Major => "G",
+            GalleryKind::Minor => "M",
+            GalleryKind::Mini => "MI",
+        }
+    }
+
+    fn prefix(&self) -> Option<&'static str> {
+        match self {
+            GalleryKind::Major => None,
+            GalleryKind::Minor => Some("minor"),
+            GalleryKind::Mini => Some("mini"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Gallery {
+    pub kind: GalleryKind,
+    pub id: String,
+}
+
+impl Gallery {
+    pub fn list_url(&self, page: u32) -> String {
+        format!("{}{}/lists/?id={}&page={page}", config::get().host, self.kind.path(), self.id)
+    }
+
+    pub fn view_url(&self, no: u64) -> String {
+        format!("{}{}/view/?id={}&no={no}&page=1", config::get().host, self.kind.path(), self.id)
+    }
+
+    // 댓글은 갤러리 종류와 상관없이 같은 주소에서 _GALLTYPE_ 으로 구분한다
+    pub fn comment_url(&self) -> String {
+        format!("{}/board/comment/", config::get().host)
+    }
+
+    pub fn gall_type(&self) -> &'static str {
+        self.kind.gall_type()
+    }
+}
+
+// 저장되는 gallery 컬럼 값. 정식 갤러리는 id 그대로, 나머지는 "minor/id", "mini/id"
+impl Display for Gallery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind.prefix() {
+            Some(prefix) => write!(f, "{}/{}", prefix, self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+impl FromStr for Gallery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = match s.trim().split_once('/') {
+            Some(("minor", id)) => (GalleryKind::Minor, id),
+            Some(("mini", id)) => (GalleryKind::Mini, id),
+            Some((prefix, _)) => bail!("unknown gallery kind {}", prefix),
+            None => (GalleryKind::Major, s.trim()),
+        };
+
+        if id.is_empty() {
+            bail!("empty gallery id {}", s);
+        }
+
+        Ok(Self {
+            kind,
+            id: id.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kinds() {
+        assert_eq!("programming".parse::<Gallery>().unwrap(), Gallery { kind: GalleryKind::Major, id: "programming".to_string() });
+        assert_eq!("minor/rust".parse::<Gallery>().unwrap(), Gallery { kind: GalleryKind::Minor, id: "rust".to_string() });
+        assert_eq!(" mini/cat ".parse::<Gallery>().unwrap(), Gallery { kind: GalleryKind::Mini, id: "cat".to_string() });
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        assert!("".parse::<Gallery>().is_err());
+        assert!("minor/".parse::<Gallery>().is_err());
+        assert!("person/someone".parse::<Gallery>().is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for v in ["programming", "minor/rust", "mini/cat"] {
+            assert_eq!(v.parse::<Gallery>().unwrap().to_string(), v);
+        }
+    }
+}
@@ -1,52 +1,141 @@
-use anyhow::Context;
+use std::collections::HashSet;
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
-use reqwest::Client;
+use reqwest::StatusCode;
 use scraper::Selector;
+use crate::article::block;
+use crate::article::count::parse_count;
+use crate::article::gallery::{Gallery, GalleryKind};
+use crate::http::profile::{Fetch, ProfileExt};
+use crate::http::proxy::{self, ProxyId};
+use crate::http::retry::RetryExt;
+use crate::http::session::Session;
+use crate::article::time::parse_kst;
 
 #[derive(Debug)]
 pub struct ArticleHeader {
     pub id: u64,
     pub title: String,
     pub has_image: bool,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub views: u64,
+    pub upvotes: u64,
+    pub comments: u64,
 }
-static PAGE_META_LIST_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".listwrap2 .ub-content.us-post").unwrap());
-static PAGE_META_LINK_SELECT: Lazy<Selector> = Lazy::new(|| Selector::parse(".gall_tit.ub-word a").unwrap());
-static PAGE_META_NUMBER_SELECT: Lazy<Selector> = Lazy::new(|| Selector::parse(".gall_num").unwrap());
-static PAGE_META_HAS_IMAGE_SELECT: Lazy<Selector> = Lazy::new(|| Selector::parse(".icon_img.icon_pic").unwrap());
 
-static URL: &'static str = "https://gall.dcinside.com/board/lists/?id=baseball_new13";
+// 과거 목록을 거슬러 올라갈 때 멈출 기준
+#[derive(Debug, Clone, Copy)]
+pub enum Cutoff {
+    Id(u64),
+    Timestamp(DateTime<Utc>),
+}
+
+impl Cutoff {
+    fn reached(&self, header: &ArticleHeader) -> bool {
+        match self {
+            Cutoff::Id(id) => header.id <= *id,
+            Cutoff::Timestamp(timestamp) => header.timestamp.is_some_and(|v| v <= *timestamp),
+        }
+    }
+}
+
+struct ListSelectors {
+    list: Selector,
+    link: Selector,
+    number: Selector,
+    has_image: Selector,
+    date: Selector,
+    views: Selector,
+    upvotes: Selector,
+    comments: Selector,
+}
+
+impl ListSelectors {
+    fn new(list: &str) -> Self {
+        Self {
+            list: Selector::parse(list).unwrap(),
+            link: Selector::parse(".gall_tit.ub-word a").unwrap(),
+            number: Selector::parse(".gall_num").unwrap(),
+            has_image: Selector::parse(".icon_img.icon_pic").unwrap(),
+            date: Selector::parse(".gall_date").unwrap(),
+            views: Selector::parse(".gall_count").unwrap(),
+            upvotes: Selector::parse(".gall_recommend").unwrap(),
+            comments: Selector::parse(".gall_tit .reply_num").unwrap(),
+        }
+    }
+
+    fn of(kind: GalleryKind) -> &'static Self {
+        match kind {
+            GalleryKind::Major => &MAJOR_LIST_SELECTORS,
+            // 마이너와 미니 갤러리는 같은 목록 구조를 쓴다
+            GalleryKind::Minor | GalleryKind::Mini => &MINOR_LIST_SELECTORS,
+        }
+    }
+}
+
+static MAJOR_LIST_SELECTORS: Lazy<ListSelectors> = Lazy::new(|| ListSelectors::new(".listwrap2 .ub-content.us-post"));
+static MINOR_LIST_SELECTORS: Lazy<ListSelectors> = Lazy::new(|| ListSelectors::new(".gall_listwrap .ub-content.us-post"));
 
 #[tracing::instrument]
-async fn http_list_page(client: Client) -> anyhow::Result<String> {
+async fn http_list_page(client: Session, gallery: &Gallery, page: u32) -> anyhow::Result<String> {
+    let url = gallery.list_url(page);
+
     let response = client
-        .get(URL)
-        .send()
+        .get(url.as_str())
+        .fetch(Fetch::Document, None)
+        .send_with_retry()
         .await
-        .context(format!("fail to request {}", URL))?;
-    
+        .context(format!("fail to request {}", url))?;
+
+    let status = response.status();
+    let proxy = ProxyId::of(&response);
+    match status {
+        StatusCode::FORBIDDEN => {
+            proxy::blocked(proxy, status.as_str());
+            bail!("blocked by dcinside ({})", status);
+        }
+        // send_with_retry 에서 이미 쉬게 했다
+        StatusCode::TOO_MANY_REQUESTS => bail!("blocked by dcinside ({})", status),
+        _ => {}
+    }
+
     let res = response.text().await
-        .context(format!("fail to read response body from {}", URL))?;
+        .context(format!("fail to read response body from {}", url))?;
+
+    if let Some(reason) = block::detect(&res) {
+        proxy::blocked(proxy, reason);
+        bail!("blocked by dcinside ({})", reason);
+    }
 
     Ok(res)
 }
 
 #[tracing::instrument]
-pub async fn collect_list(client: Client) -> anyhow::Result<Vec<ArticleHeader>> {
-    let html = http_list_page(client).await.context("fail to collect list page")?;
+pub async fn collect_list(client: Session, gallery: &Gallery, page: u32) -> anyhow::Result<Vec<ArticleHeader>> {
+    let html = http_list_page(client, gallery, page).await.context("fail to collect list page")?;
     let dom = scraper::Html::parse_document(&html);
+    let selectors = ListSelectors::of(gallery.kind);
     let mut result = Vec::<ArticleHeader>::new();
 
-    for el in dom.select(&PAGE_META_LIST_SELECTOR) {
-        let link_sl = el.select(&PAGE_META_LINK_SELECT).next();
-        let number_sl = el.select(&PAGE_META_NUMBER_SELECT).next();
-        let has_image_sl = el.select(&PAGE_META_HAS_IMAGE_SELECT).next();
+    for el in dom.select(&selectors.list) {
+        let link_sl = el.select(&selectors.link).next();
+        let number_sl = el.select(&selectors.number).next();
+        let has_image_sl = el.select(&selectors.has_image).next();
+        let date_sl = el.select(&selectors.date).next();
         let Some(link_el) = link_sl else { continue; };
         let Some(number_el) = number_sl else { continue; };
 
         let meta = ArticleHeader {
             id: number_el.text().collect::<String>().parse::<u64>()?,
             title: link_el.text().collect::<String>().replace("\n", "").replace("\t", ""),
-            has_image: has_image_sl != None,
+            has_image: has_image_sl.is_some(),
+            timestamp: date_sl
+                .and_then(|v| v.value().attr("title"))
+                .and_then(parse_kst),
+            views: parse_count(el.select(&selectors.views).next()),
+            upvotes: parse_count(el.select(&selectors.upvotes).next()),
+            comments: parse_count(el.select(&selectors.comments).next()),
         };
 
         result.push(meta);
@@ -54,3 +143,30 @@ pub async fn collect_list(client: Client) -> anyhow::Result<Vec<ArticleHeader>>
 
     Ok(result)
 }
+
+// 1페이지부터 cutoff 에 닿을 때까지 목록을 넘기며 수집한다
+// 넘기는 사이 새 글이 올라오면 같은 글이 다음 페이지에 다시 나오므로 번호로 한 번만 담는다
+#[tracing::instrument]
+pub async fn collect_until(client: Session, gallery: &Gallery, cutoff: Cutoff, max_page: u32) -> anyhow::Result<Vec<ArticleHeader>> {
+    let mut result = Vec::<ArticleHeader>::new();
+    let mut ids = HashSet::<u64>::new();
+
+    for page in 1..=max_page {
+        let headers = collect_list(client.clone(), gallery, page)
+            .await
+            .context(format!("fail to collect list page {}", page))?;
+
+        if headers.is_empty() {
+            break;
+        }
+
+        let reached = headers.iter().any(|h| cutoff.reached(h));
+        result.extend(headers.into_iter().filter(|h| !cutoff.reached(h) && ids.insert(h.id)));
+
+        if reached {
+            break;
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,113 @@
+// 첨부 파일 앞부분의 시그니처로 판별한 형식
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileType {
+    pub mime: &'static str,
+    pub ext: &'static str,
+}
+
+// 판별하지 못한 파일
+pub const UNKNOWN: FileType = FileType { mime: "application/octet-stream", ext: "bin" };
+
+const JPEG: FileType = FileType { mime: "image/jpeg", ext: "jpg" };
+const PNG: FileType = FileType { mime: "image/png", ext: "png" };
+const GIF: FileType = FileType { mime: "image/gif", ext: "gif" };
+const WEBP: FileType = FileType { mime: "image/webp", ext: "webp" };
+const BMP: FileType = FileType { mime: "image/bmp", ext: "bmp" };
+const AVIF: FileType = FileType { mime: "image/avif", ext: "avif" };
+const HEIC: FileType = FileType { mime: "image/heic", ext: "heic" };
+const MP4: FileType = FileType { mime: "video/mp4", ext: "mp4" };
+const MOV: FileType = FileType { mime: "video/quicktime", ext: "mov" };
+const WEBM: FileType = FileType { mime: "video/webm", ext: "webm" };
+
+// 판별에 필요한 최대 바이트 수
+pub const SNIFF_LEN: usize = 18;
+
+pub fn sniff(bytes: &[u8]) -> Option<FileType> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some(JPEG),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(PNG),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(GIF),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(WEBP),
+        // BMP: 파일 크기 뒤 예약 4바이트는 0, 픽셀 위치 뒤에 DIB 헤더 크기
+        [b'B', b'M', _, _, _, _, 0, 0, 0, 0, _, _, _, _, a, b, c, d, ..] => {
+            match u32::from_le_bytes([*a, *b, *c, *d]) {
+                12 | 40 | 52 | 56 | 64 | 108 | 124 => Some(BMP),
+                _ => None,
+            }
+        }
+        [0x1A, 0x45, 0xDF, 0xA3, ..] => Some(WEBM),
+        // ISO BMFF: 4바이트 박스 크기 뒤 ftyp 와 major brand
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] => match brand.get(..4)? {
+            b"avif" | b"avis" => Some(AVIF),
+            b"heic" | b"heix" | b"mif1" | b"msf1" => Some(HEIC),
+            b"qt  " => Some(MOV),
+            b"isom" | b"iso2" | b"mp41" | b"mp42" | b"avc1" | b"dash" => Some(MP4),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut v = vec![0, 0, 0, 0x20];
+        v.extend_from_slice(b"ftyp");
+        v.extend_from_slice(brand);
+        v.extend_from_slice(&[0; 6]);
+        v
+    }
+
+    fn bmp(reserved: [u8; 4], dib: u32) -> Vec<u8> {
+        let mut v = b"BM".to_vec();
+        v.extend_from_slice(&1000u32.to_le_bytes());
+        v.extend_from_slice(&reserved);
+        v.extend_from_slice(&54u32.to_le_bytes());
+        v.extend_from_slice(&dib.to_le_bytes());
+        v
+    }
+
+    #[test]
+    fn sniff_signatures() {
+        assert_eq!(sniff(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(JPEG));
+        assert_eq!(sniff(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]), Some(PNG));
+        assert_eq!(sniff(b"GIF87a"), Some(GIF));
+        assert_eq!(sniff(b"GIF89a"), Some(GIF));
+        assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "), Some(WEBP));
+        assert_eq!(sniff(&[0x1A, 0x45, 0xDF, 0xA3]), Some(WEBM));
+        assert_eq!(sniff(&bmp([0; 4], 40)), Some(BMP));
+        assert_eq!(sniff(&bmp([0; 4], 124)), Some(BMP));
+    }
+
+    #[test]
+    fn sniff_ftyp_brands() {
+        for brand in [b"isom", b"iso2", b"mp41", b"mp42", b"avc1", b"dash"] {
+            assert_eq!(sniff(&ftyp(brand)), Some(MP4));
+        }
+        assert_eq!(sniff(&ftyp(b"avif")), Some(AVIF));
+        assert_eq!(sniff(&ftyp(b"heic")), Some(HEIC));
+        assert_eq!(sniff(&ftyp(b"mif1")), Some(HEIC));
+        assert_eq!(sniff(&ftyp(b"qt  ")), Some(MOV));
+        assert_eq!(sniff(&ftyp(b"crx ")), None);
+        assert_eq!(sniff(&ftyp(b"3gp4")), None);
+    }
+
+    #[test]
+    fn sniff_bmp_header() {
+        assert_eq!(sniff(&bmp([0, 0, 1, 0], 40)), None);
+        assert_eq!(sniff(&bmp([0; 4], 41)), None);
+        assert_eq!(sniff(b"BM this is text"), None);
+    }
+
+    #[test]
+    fn sniff_truncated() {
+        assert_eq!(sniff(&[]), None);
+        assert_eq!(sniff(&[0xFF, 0xD8]), None);
+        assert_eq!(sniff(b"GIF8"), None);
+        assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEB"), None);
+        assert_eq!(sniff(&ftyp(b"isom")[..10]), None);
+        assert_eq!(sniff(&bmp([0; 4], 40)[..SNIFF_LEN - 1]), None);
+    }
+}
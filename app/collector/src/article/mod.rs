@@ -1,3 +1,12 @@
 pub mod list;
+#[allow(clippy::module_inception)]
 pub mod article;
 pub mod attach;
+pub mod block;
+pub mod body;
+pub mod comment;
+pub mod count;
+pub mod error;
+pub mod gallery;
+pub mod mime;
+pub mod time;
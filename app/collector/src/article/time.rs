@@ -0,0 +1,69 @@
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+
+// 디시인사이드는 KST(Asia/Seoul, 서머타임 없음) 기준으로 시각을 표시한다
+static KST: Lazy<FixedOffset> = Lazy::new(|| FixedOffset::east_opt(9 * 3600).unwrap());
+
+pub fn parse_kst(v: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(v.trim(), "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(v.trim(), "%Y.%m.%d %H:%M:%S"))
+        .ok()?;
+
+    KST.from_local_datetime(&naive)
+        .single()
+        .map(|v| v.with_timezone(&Utc))
+}
+
+// 올해 작성된 댓글은 "05.01 12:34:56" 처럼 연도 없이 표시된다
+pub fn parse_kst_without_year(v: &str) -> Option<DateTime<Utc>> {
+    parse_kst_without_year_at(v, Utc::now())
+}
+
+fn parse_kst_without_year_at(v: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Some(v) = parse_kst(v) {
+        return Some(v);
+    }
+
+    let year = now.with_timezone(&*KST).year();
+    let parsed = parse_kst(&format!("{}.{}", year, v.trim()))?;
+
+    // 연말에 작성된 댓글을 연초에 읽으면 작년 시각이다
+    if parsed > now {
+        return parse_kst(&format!("{}.{}", year - 1, v.trim()));
+    }
+
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(v: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(v).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parse_with_year() {
+        assert_eq!(parse_kst("2024-05-01 12:34:56"), Some(utc("2024-05-01T03:34:56Z")));
+        assert_eq!(parse_kst("2024.05.01 12:34:56"), Some(utc("2024-05-01T03:34:56Z")));
+        assert_eq!(parse_kst("05.01 12:34:56"), None);
+    }
+
+    #[test]
+    fn parse_without_year_this_year() {
+        let now = utc("2024-05-02T00:00:00Z");
+
+        assert_eq!(parse_kst_without_year_at("05.01 12:34:56", now), Some(utc("2024-05-01T03:34:56Z")));
+        assert_eq!(parse_kst_without_year_at("2023.05.01 12:34:56", now), Some(utc("2023-05-01T03:34:56Z")));
+    }
+
+    #[test]
+    fn parse_without_year_rollover() {
+        // KST 로는 2025-01-01 00:30
+        let now = utc("2024-12-31T15:30:00Z");
+
+        assert_eq!(parse_kst_without_year_at("12.31 23:50:00", now), Some(utc("2024-12-31T14:50:00Z")));
+        assert_eq!(parse_kst_without_year_at("01.01 00:10:00", now), Some(utc("2024-12-31T15:10:00Z")));
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use entity::Entity;
+use serde::{Deserialize, Serialize};
+use crate::article::gallery::Gallery;
+
+// 목록에 올라오기 전에 삭제되었거나 존재하지 않는 게시글 번호
+#[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
+#[entity(table = "article_missing")]
+pub struct MissingArticle {
+    #[serde(with = "clickhouse::serde::uuid")]
+    uid: uuid::Uuid,
+    gallery: String,
+    id: u64,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
+    timestamp: DateTime<Utc>,
+}
+
+impl From<(&Gallery, u64)> for MissingArticle {
+    fn from(v: (&Gallery, u64)) -> Self {
+        let (gallery, id) = v;
+        Self {
+            uid: uuid::Uuid::now_v7(),
+            gallery: gallery.to_string(),
+            id,
+            timestamp: Utc::now(),
+        }
+    }
+}
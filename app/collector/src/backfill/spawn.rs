@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use anyhow::{bail, Context};
+use chrono::Utc;
+use clickhouse::sql::Identifier;
+use entity::Entity;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::instrument;
+use clickhouse_entity::batch;
+use util::report;
+use util::shutdown::Shutdown;
+use crate::article::gallery::Gallery;
+use crate::article::article::{get_article, ArticleResult};
+use crate::article::list::{collect_list, collect_until, Cutoff};
+use crate::backfill::entity::MissingArticle;
+use crate::comment::CommentStore;
+use crate::config;
+use crate::create_article;
+use crate::http::limit::Priority;
+use crate::http::session::Session;
+
+const BOOTSTRAP_DAYS: i64 = 1;
+const BOOTSTRAP_MAX_PAGE: u32 = 1000;
+
+// 채운 글, 글의 댓글, 지워져서 없는 번호를 남기는 곳
+#[derive(Clone, Copy)]
+struct Stores<'a> {
+    article: &'a create_article::ArticleStore,
+    comment: &'a CommentStore,
+    missing: &'a batch::Batch<MissingArticle>,
+}
+
+pub struct Backfill {
+    close_sender: oneshot::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
+
+#[async_trait::async_trait]
+impl Shutdown for Backfill {
+    async fn shutdown(self) {
+        if self.close_sender.send(()).is_err() {
+            println!("backfill send close fail");
+        }
+
+        if let Some(err) = self.join_handle.await.err() {
+            println!("backfill wait close fail {}", err);
+        }
+    }
+}
+
+#[instrument(skip(http_client, clickhouse_client, comment_store))]
+pub async fn run(
+    http_client: Session,
+    clickhouse_client: clickhouse::Client,
+    comment_store: CommentStore,
+    gallery: Gallery,
+) -> anyhow::Result<impl Shutdown> {
+    let cursor = max_stored_id(&clickhouse_client, &gallery).await?;
+    let article_store = create_article::ArticleStore::run(clickhouse_client.clone(), config::get().batch_options()).await;
+    let missing_batch: batch::Batch<MissingArticle> = batch::Batch::run(clickhouse_client.clone(), config::get().batch_options()).await;
+    let (close_sender, mut close_receiver) = oneshot::channel::<()>();
+
+    let join_handle = tokio::spawn(Priority::Normal.scope(async move {
+        let stores = Stores { article: &article_store, comment: &comment_store, missing: &missing_batch };
+        tokio::select! {
+            _ = backfill(http_client, &clickhouse_client, &gallery, stores, cursor) => {}
+            _ = &mut close_receiver => {}
+        }
+
+        article_store.shutdown().await;
+        missing_batch.shutdown().await;
+    }));
+
+    Ok(Backfill {
+        close_sender,
+        join_handle,
+    })
+}
+
+// 목록 1페이지보다 아래로 밀려난 번호 중 저장되지 않은 번호를 채운다
+async fn backfill(
+    http_client: Session,
+    clickhouse_client: &clickhouse::Client,
+    gallery: &Gallery,
+    stores: Stores<'_>,
+    mut cursor: u64,
+) {
+    let mut poll = tokio::time::interval(config::get().poll.backfill());
+
+    loop {
+        poll.tick().await;
+
+        let headers = match collect_list(http_client.clone(), gallery, 1).await {
+            Ok(v) => v,
+            Err(e) => {
+                report!(e, "Failed to get headers");
+                continue;
+            }
+        };
+
+        let Some(list_tail) = headers.iter().map(|h| h.id).min() else { continue; };
+
+        // 저장된 게시글이 없다면 최근 BOOTSTRAP_DAYS 일치 목록의 가장 오래된 번호부터 채운다
+        if cursor == 0 {
+            cursor = match bootstrap_cursor(http_client.clone(), gallery).await {
+                Ok(v) => v.unwrap_or(list_tail).saturating_sub(1),
+                Err(e) => {
+                    report!(e, "Failed to bootstrap cursor");
+                    continue;
+                }
+            };
+        }
+
+        if cursor + 1 >= list_tail {
+            continue;
+        }
+
+        tracing::info!(from = cursor + 1, to = list_tail - 1, "backfill gap");
+
+        cursor = match fill(http_client.clone(), clickhouse_client, gallery, stores, cursor, list_tail).await {
+            Ok(v) => v,
+            Err(e) => {
+                report!(e, "Failed to get stored ids");
+                continue;
+            }
+        };
+    }
+}
+
+// after 와 before 사이(양 끝 제외) 번호 중 저장되지 않은 글을 채우고, 처리한 마지막 번호를 돌려준다
+async fn fill(
+    http_client: Session,
+    clickhouse_client: &clickhouse::Client,
+    gallery: &Gallery,
+    stores: Stores<'_>,
+    after: u64,
+    before: u64,
+) -> anyhow::Result<u64> {
+    let stored = stored_ids(clickhouse_client, gallery, after, before).await?;
+    let mut cursor = after;
+
+    for id in (after + 1)..before {
+        if stored.contains(&id) {
+            cursor = id;
+            continue;
+        }
+
+        match get_article(http_client.clone(), gallery, id).await {
+            Ok(ArticleResult::Article(article)) => {
+                if let Err(e) = create_article::store(http_client.clone(), gallery, stores.article, stores.comment, article).await {
+                    report!(e, "Failed to store article");
+                    break;
+                }
+            }
+            Ok(ArticleResult::DeletedArticle) => {
+                if let Err(e) = stores.missing.insert((gallery, id).into()).await {
+                    report!(e, "Failed to insert missing article");
+                    break;
+                }
+            }
+            Err(e) if e.is_transient() => {
+                let e = anyhow::Error::from(e);
+                report!(e, "Failed to get article");
+                break;
+            }
+            Err(e) => {
+                let e = anyhow::Error::from(e);
+                report!(e, "Failed to parse article");
+            }
+        }
+
+        cursor = id;
+    }
+
+    Ok(cursor)
+}
+
+// 지정한 번호 구간 [from, to] 를 한 번 채우고 끝낸다
+#[instrument(skip(http_client, clickhouse_client))]
+pub async fn range(
+    http_client: Session,
+    clickhouse_client: clickhouse::Client,
+    gallery: Gallery,
+    from: u64,
+    to: u64,
+) -> anyhow::Result<()> {
+    let article_store = create_article::ArticleStore::run(clickhouse_client.clone(), config::get().batch_options()).await;
+    let comment_store = CommentStore::run(clickhouse_client.clone(), config::get().batch_options()).await;
+    let missing_batch: batch::Batch<MissingArticle> = batch::Batch::run(clickhouse_client.clone(), config::get().batch_options()).await;
+
+    let stores = Stores { article: &article_store, comment: &comment_store, missing: &missing_batch };
+    let result = fill(http_client, &clickhouse_client, &gallery, stores, from.saturating_sub(1), to.saturating_add(1)).await;
+
+    article_store.shutdown().await;
+    comment_store.shutdown().await;
+    missing_batch.shutdown().await;
+
+    let cursor = result?;
+    if cursor < to {
+        bail!("backfill stopped at {}", cursor);
+    }
+
+    Ok(())
+}
+
+async fn bootstrap_cursor(http_client: Session, gallery: &Gallery) -> anyhow::Result<Option<u64>> {
+    let cutoff = Cutoff::Timestamp(Utc::now() - chrono::Duration::days(BOOTSTRAP_DAYS));
+    let headers = collect_until(http_client, gallery, cutoff, BOOTSTRAP_MAX_PAGE)
+        .await
+        .context("fail to collect bootstrap list")?;
+
+    Ok(headers.iter().map(|h| h.id).min())
+}
+
+async fn max_stored_id(client: &clickhouse::Client, gallery: &Gallery) -> anyhow::Result<u64> {
+    client
+        .query("SELECT greatest((SELECT max(id) FROM ? WHERE gallery = ?), (SELECT max(id) FROM ? WHERE gallery = ?))")
+        .bind(Identifier(create_article::entity::Article::table_name()))
+        .bind(gallery.to_string())
+        .bind(Identifier(MissingArticle::table_name()))
+        .bind(gallery.to_string())
+        .fetch_one::<u64>()
+        .await
+        .context("fail to fetch max stored id")
+}
+
+async fn stored_ids(client: &clickhouse::Client, gallery: &Gallery, after: u64, before: u64) -> anyhow::Result<HashSet<u64>> {
+    let ids = client
+        .query("SELECT id FROM ? WHERE gallery = ? AND id > ? AND id < ? UNION ALL SELECT id FROM ? WHERE gallery = ? AND id > ? AND id < ?")
+        .bind(Identifier(create_article::entity::Article::table_name()))
+        .bind(gallery.to_string())
+        .bind(after)
+        .bind(before)
+        .bind(Identifier(MissingArticle::table_name()))
+        .bind(gallery.to_string())
+        .bind(after)
+        .bind(before)
+        .fetch_all::<u64>()
+        .await
+        .context("fail to fetch stored ids")?;
+
+    Ok(ids.into_iter().collect())
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use tracing_subscriber::filter::LevelFilter;
+
+#[derive(Debug, Parser)]
+#[command(name = "collector", about = "dcinside gallery collector")]
+pub struct Cli {
+    /// 설정 파일 경로 (없으면 COLLECTOR_CONFIG, ./collector.toml 순서로 찾는다)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// off, error, warn, info, debug, trace
+    #[arg(long, global = true, default_value = "info")]
+    pub log_level: LevelFilter,
+
+    // 생략하면 watch
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 새 글 수집, 누락 채우기, 카운터 스냅샷, 삭제 확인을 종료 신호가 올 때까지 실행한다
+    Watch,
+    /// 최근 수집한 글이 삭제되었는지 한 번 확인하고 article_deleted 에 기록한다
+    MonitorDeletes {
+        /// 설정의 galleries 대신 이 갤러리만 확인한다
+        #[arg(long)]
+        gallery: Option<String>,
+        /// 작성된 지 이 시간 이내인 글만 확인한다
+        #[arg(long, default_value_t = 72)]
+        hours: u32,
+    },
+    /// 게시글 번호 구간 [from, to] 중 저장되지 않은 글을 채운다
+    Backfill {
+        /// 생략하면 설정의 첫 번째 갤러리
+        #[arg(long)]
+        gallery: Option<String>,
+        #[arg(long)]
+        from: u64,
+        #[arg(long)]
+        to: u64,
+    },
+    /// 게시글 하나를 수집해 JSON 으로 출력한다. 저장하지 않는다
+    Fetch {
+        id: u64,
+        /// 생략하면 설정의 첫 번째 갤러리
+        #[arg(long)]
+        gallery: Option<String>,
+    },
+    /// ClickHouse 데이터베이스와 테이블을 만든다
+    Migrate,
+}
@@ -1,38 +1,191 @@
-use crate::collector::article::{is_article_deleted, DeletedArticle};
-use crate::collector::entity;
-use crate::collector::{article, delete_entity};
+use std::collections::HashMap;
+use std::time::Duration;
 use anyhow::Context;
+use chrono::Utc;
+use clickhouse::sql::Identifier;
+use entity::Entity;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::instrument;
+use util::report;
+use clickhouse_entity::batch::Batch;
+use util::shutdown::Shutdown;
+use crate::article::article::{get_article, ArticleResult, DeletedArticle};
+use crate::article::error::ArticleError;
+use crate::article::gallery::Gallery;
+use crate::collector::delete_entity::Delete;
+use crate::config;
+use crate::create_article;
+use crate::http::limit::Priority;
+use crate::http::session::Session;
+use crate::CLICKHOUSE_CLIENT;
 
-pub async fn collect_delete() -> anyhow::Result<()> {
-    let articles = entity::get_monitor_target()
-        .await
-        .with_context(|| "fail get_monitor_target")?;
+// 작성 후 FRESH_AGE 까지는 FRESH_INTERVAL 마다, TRACK_AGE 까지는 STALE_INTERVAL 마다 삭제 여부를 다시 확인한다
+const FRESH_AGE: Duration = Duration::from_secs(60 * 60);
+const FRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const TRACK_AGE: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+const STALE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// 한 주기에 확인하는 최대 글 수. 남은 글은 다음 주기로 넘긴다
+const MAX_CHECK_PER_CYCLE: usize = 200;
 
-    println!("{} articles monitor.", articles.len());
+pub struct DeleteMonitor {
+    close_sender: oneshot::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
 
-    let articles = {
-        let mut out = Vec::with_capacity(articles.len());
-        for a in articles {
-            let deleted_result = is_article_deleted(a).await?;
-            if let article::CollectArticleResult::DeletedArticle(d) = deleted_result
-            {
-                out.push(d);
-            }
+#[async_trait::async_trait]
+impl Shutdown for DeleteMonitor {
+    async fn shutdown(self) {
+        if self.close_sender.send(()).is_err() {
+            println!("delete monitor send close fail");
+        }
 
-            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        if let Some(err) = self.join_handle.await.err() {
+            println!("delete monitor wait close fail {}", err);
         }
-        out
-    };
+    }
+}
+
+#[instrument(skip(http_client))]
+pub async fn run(http_client: Session, gallery: Gallery) -> anyhow::Result<impl Shutdown> {
+    let batch: Batch<Delete> = Batch::run(CLICKHOUSE_CLIENT.clone(), config::get().batch_options()).await;
+    let (close_sender, mut close_receiver) = oneshot::channel::<()>();
 
-    let len = articles.iter().count();
+    let join_handle = tokio::spawn(Priority::Low.scope(async move {
+        tokio::select! {
+            _ = monitor(http_client, &gallery, &batch) => {}
+            _ = &mut close_receiver => {}
+        }
 
-    println!("{} articles are deleted.", len);
+        batch.shutdown().await;
+    }));
+
+    Ok(DeleteMonitor {
+        close_sender,
+        join_handle,
+    })
+}
 
-    if len == 0 {
-        return Ok(())
+fn check_interval(age: Duration) -> Duration {
+    if age < FRESH_AGE {
+        FRESH_INTERVAL
+    } else {
+        STALE_INTERVAL
     }
+}
+
+async fn monitor(http_client: Session, gallery: &Gallery, batch: &Batch<Delete>) {
+    let mut poll = tokio::time::interval(FRESH_INTERVAL);
+    let mut last_checked = HashMap::<u64, Instant>::new();
+
+    loop {
+        poll.tick().await;
+
+        let targets = match get_monitor_target(gallery, TRACK_AGE).await {
+            Ok(v) => v,
+            Err(e) => {
+                report!(e, "Failed to get monitor target");
+                continue;
+            }
+        };
+
+        last_checked.retain(|id, _| targets.contains_key(id));
 
-    delete_entity::insert(articles.into_iter()).await?;
+        let now = Instant::now();
+        let mut due = targets
+            .into_iter()
+            .filter(|(id, age)| {
+                last_checked
+                    .get(id)
+                    .is_none_or(|checked| now.duration_since(*checked) >= check_interval(*age))
+            })
+            .collect::<Vec<_>>();
+        // 삭제될 가능성이 높은 최근 글부터 본다
+        due.sort_unstable_by_key(|(id, age)| (*age, *id));
+        due.truncate(MAX_CHECK_PER_CYCLE);
+
+        let due = due.into_iter().map(|(id, _)| id).collect();
+        for (id, checked) in check(http_client.clone(), gallery, batch, due).await {
+            last_checked.insert(id, checked);
+        }
+    }
+}
+
+// 작성된 지 max_age 이내이고 아직 삭제 기록이 없는 글과 작성 후 지난 시간
+async fn get_monitor_target(gallery: &Gallery, max_age: Duration) -> anyhow::Result<HashMap<u64, Duration>> {
+    let rows = CLICKHOUSE_CLIENT
+        .query("SELECT id, toUInt64(greatest(dateDiff('second', min(created_at), now64(6)), 0)) FROM ? WHERE gallery = ? AND created_at >= now64(6) - toIntervalSecond(?) AND id NOT IN (SELECT id FROM ? WHERE gallery = ?) GROUP BY id")
+        .bind(Identifier(create_article::entity::Article::table_name()))
+        .bind(gallery.to_string())
+        .bind(max_age.as_secs())
+        .bind(Identifier(Delete::table_name()))
+        .bind(gallery.to_string())
+        .fetch_all::<(u64, u64)>()
+        .await
+        .context("fail to fetch monitor target")?;
+
+    Ok(rows.into_iter().map(|(id, age)| (id, Duration::from_secs(age))).collect())
+}
+
+// 작성된 지 hours 시간 이내인 글을 한 번 확인한다
+pub async fn collect_delete(http_client: Session, gallery: &Gallery, hours: u32) -> anyhow::Result<()> {
+    let targets = get_monitor_target(gallery, Duration::from_secs(hours as u64 * 60 * 60))
+        .await
+        .with_context(|| "fail get_monitor_target")?;
+
+    let mut targets = targets.into_iter().collect::<Vec<_>>();
+    targets.sort_unstable_by_key(|(id, age)| (*age, *id));
+    let ids = targets.into_iter().map(|(id, _)| id).collect();
+
+    let batch: Batch<Delete> = Batch::run(CLICKHOUSE_CLIENT.clone(), config::get().batch_options()).await;
+    check(http_client, gallery, &batch, ids).await;
+    batch.shutdown().await;
 
     Ok(())
 }
+
+// 삭제된 글은 batch 로 article_deleted 에 기록하고, 확인을 마친 번호와 확인한 시각을 돌려준다
+// 기록하지 못한 글은 확인하지 않은 것으로 두어 다음 주기에 다시 본다
+async fn check(http_client: Session, gallery: &Gallery, batch: &Batch<Delete>, ids: Vec<u64>) -> Vec<(u64, Instant)> {
+    tracing::info!(gallery = %gallery, count = ids.len(), "articles monitor");
+
+    let mut checked = Vec::with_capacity(ids.len());
+    let mut deleted = 0;
+
+    for id in ids {
+        match get_article(http_client.clone(), gallery, id).await {
+            Ok(ArticleResult::DeletedArticle) => {
+                let article = DeletedArticle {
+                    gallery: gallery.to_string(),
+                    id,
+                    timestamp: Utc::now(),
+                };
+
+                if let Err(e) = batch.insert(article.into()).await {
+                    report!(e, "Failed to insert article deleted");
+                    continue;
+                }
+                deleted += 1;
+            }
+            Ok(ArticleResult::Article(_)) => {}
+            // 나머지 글은 COOL_DOWN 이 풀린 뒤 다음 확인에서 본다
+            Err(ArticleError::Blocked) => break,
+            Err(e) => {
+                let transient = e.is_transient();
+                let e = anyhow::Error::from(e);
+                report!(e, "Failed to check article deleted");
+
+                if transient {
+                    continue;
+                }
+            }
+        }
+
+        checked.push((id, Instant::now()));
+    }
+
+    tracing::info!(gallery = %gallery, count = deleted, "articles are deleted");
+
+    checked
+}
@@ -1,22 +1,14 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
-use crate::collector::{article, CLICKHOUSE_CLIENT};
-pub const TABLE_NAME: &str = "article_deleted";
+use entity::Entity;
+use serde::{Deserialize, Serialize};
+use crate::article::article;
 
-pub async fn insert(rows: impl Iterator<Item=article::DeletedArticle>) -> anyhow::Result<()> {
-    let mut insert: clickhouse::insert::Insert<Delete> = CLICKHOUSE_CLIENT.insert(TABLE_NAME).await?;
-    for row in rows {
-        insert.write(&row.into()).await?
-    }
-    insert.end().await?;
-
-    Ok(())
-}
-
-#[derive(Debug, Serialize, clickhouse::Row)]
+#[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
+#[entity(table = "article_deleted")]
 pub struct Delete {
     #[serde(with = "clickhouse::serde::uuid")]
     uid: uuid::Uuid,
+    gallery: String,
     id: u64,
     #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
     timestamp: DateTime<Utc>,
@@ -27,6 +19,7 @@ impl From<article::DeletedArticle> for Delete {
     fn from(v: article::DeletedArticle) -> Self {
         Self {
             uid: uuid::Uuid::now_v7(),
+            gallery: v.gallery,
             id: v.id,
             timestamp: v.timestamp,
         }
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use entity::Entity;
+use serde::{Deserialize, Serialize};
+use crate::article::{article, comment};
+
+#[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
+#[entity(table = "comment")]
+pub struct Comment {
+    #[serde(with = "clickhouse::serde::uuid")]
+    uid: uuid::Uuid,
+    gallery: String,
+    article_id: u64,
+    no: u64,
+    parent_no: Option<u64>,
+    depth: u64,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
+    timestamp: DateTime<Utc>,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros::option")]
+    created_at: Option<DateTime<Utc>>,
+    author: String,
+    author_uid: Option<String>,
+    author_ip: Option<String>,
+    content: String,
+    content_html: String,
+    deleted: bool,
+}
+
+impl From<(&article::Article, comment::Comment)> for Comment {
+    fn from(v: (&article::Article, comment::Comment)) -> Self {
+        let (article, comment) = v;
+        Self {
+            uid: uuid::Uuid::now_v7(),
+            gallery: article.gallery.clone(),
+            article_id: article.id,
+            no: comment.no,
+            parent_no: comment.parent_no,
+            depth: comment.depth,
+            timestamp: Utc::now(),
+            created_at: comment.created_at,
+            author: comment.author,
+            author_uid: comment.author_uid,
+            author_ip: comment.author_ip,
+            content: comment.content,
+            content_html: comment.content_html,
+            deleted: comment.deleted,
+        }
+    }
+}
+
+// 저장해 둔 댓글이 목록에서 사라졌거나 삭제 표시로 바뀐 것을 확인한 시각
+#[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
+#[entity(table = "comment_deleted")]
+pub struct CommentDeleted {
+    #[serde(with = "clickhouse::serde::uuid")]
+    uid: uuid::Uuid,
+    gallery: String,
+    article_id: u64,
+    no: u64,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
+    timestamp: DateTime<Utc>,
+}
+
+impl From<(&article::Article, u64)> for CommentDeleted {
+    fn from(v: (&article::Article, u64)) -> Self {
+        let (article, no) = v;
+        Self {
+            uid: uuid::Uuid::now_v7(),
+            gallery: article.gallery.clone(),
+            article_id: article.id,
+            no,
+            timestamp: Utc::now(),
+        }
+    }
+}
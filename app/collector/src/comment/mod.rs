@@ -0,0 +1,4 @@
+pub(crate) mod entity;
+mod store;
+
+pub use store::CommentStore;
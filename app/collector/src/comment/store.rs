@@ -0,0 +1,178 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{bail, Context};
+use clickhouse::sql::Identifier;
+use clickhouse_entity::batch::{Batch, BatchOptions};
+use entity::Entity;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use util::shutdown::Shutdown;
+use crate::article::article::Article;
+use crate::article::comment::{collect_comments, Comments};
+use crate::article::gallery::Gallery;
+use crate::comment::entity::{Comment, CommentDeleted};
+use crate::http::session::Session;
+
+// 이 수를 넘으면 FORGET_AFTER 동안 다시 보지 않은 글의 상태를 지운다. 지운 글은 다음에 볼 때 DB 에서 다시 읽는다
+const CAPACITY: usize = 10000;
+const FORGET_AFTER: Duration = Duration::from_secs(24 * 3600);
+
+// 글마다 저장한 댓글 번호와 삭제로 남긴 댓글 번호
+struct Known {
+    stored: HashSet<u64>,
+    deleted: HashSet<u64>,
+    touched: Instant,
+}
+
+// 새 글 저장, 백필, 스냅샷이 갤러리마다 하나를 나눠 쓴다. 같은 글을 여러 곳에서 봐도 한 번만 저장한다
+#[derive(Clone)]
+pub struct CommentStore {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    client: clickhouse::Client,
+    known: Mutex<HashMap<u64, Known>>,
+    comment: Batch<Comment>,
+    deleted: Batch<CommentDeleted>,
+}
+
+// 나눠 쓰던 곳이 모두 내려간 뒤에 부른다
+#[async_trait::async_trait]
+impl Shutdown for CommentStore {
+    async fn shutdown(self) {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => {
+                inner.comment.shutdown().await;
+                inner.deleted.shutdown().await;
+            }
+            Err(_) => println!("comment store is still in use"),
+        }
+    }
+}
+
+impl CommentStore {
+    pub async fn run(client: clickhouse::Client, options: BatchOptions) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                comment: Batch::run(client.clone(), options).await,
+                deleted: Batch::run(client.clone(), options).await,
+                known: Mutex::new(HashMap::new()),
+                client,
+            }),
+        }
+    }
+
+    // 글의 댓글을 모두 읽어 처음 본 댓글은 저장하고, 저장해 둔 댓글이 사라졌거나 삭제 표시로 바뀌었으면 삭제로 남긴다
+    // known 은 비교하는 동안만 잠근다. DB 조회와 batch 로 보내는 동안에는 잠그지 않는다
+    pub async fn sync(&self, http_client: Session, gallery: &Gallery, article: &Article) -> anyhow::Result<()> {
+        let Comments { comments, complete } = if article.counter.comments == 0 {
+            Comments { comments: Vec::new(), complete: true }
+        } else {
+            let Some(e_s_n_o) = article.e_s_n_o.as_deref() else {
+                bail!("not found e_s_n_o id: {}", article.id);
+            };
+            collect_comments(http_client, gallery, article.id, e_s_n_o).await?
+        };
+
+        // 처음 보는 글이면 잠그지 않은 채로 읽어 둔다. 그 사이 다른 곳에서 먼저 읽었으면 그쪽을 쓴다
+        let loaded = if self.inner.known.lock().await.contains_key(&article.id) {
+            None
+        } else {
+            Some(self.load(gallery, article.id).await?)
+        };
+
+        // 댓글 수가 있는데 하나도 읽지 못했다면 응답이 이상한 것으로 보고 삭제를 남기지 않는다
+        let complete = complete && (!comments.is_empty() || article.counter.comments == 0);
+        let mut seen = HashSet::with_capacity(comments.len());
+        let mut inserts = Vec::new();
+        let mut deletes = Vec::new();
+
+        {
+            let mut known = self.inner.known.lock().await;
+            if known.len() > CAPACITY {
+                known.retain(|id, v| *id == article.id || v.touched.elapsed() < FORGET_AFTER);
+            }
+
+            let state = match (known.entry(article.id), loaded) {
+                (Entry::Occupied(v), _) => v.into_mut(),
+                (Entry::Vacant(v), Some(loaded)) => v.insert(loaded),
+                // 읽어 둔 뒤 잠그기 전에 지워졌다
+                (Entry::Vacant(_), None) => bail!("comment state evicted id: {}", article.id),
+            };
+            state.touched = Instant::now();
+
+            for comment in comments {
+                let no = comment.no;
+                let deleted = comment.deleted;
+                seen.insert(no);
+
+                if state.stored.insert(no) {
+                    // 처음 볼 때부터 삭제 표시였다면 삭제 시각은 알 수 없다
+                    if deleted {
+                        state.deleted.insert(no);
+                    }
+                    inserts.push(comment);
+                } else if deleted && state.deleted.insert(no) {
+                    deletes.push(no);
+                }
+            }
+
+            // 전체를 읽지 못했으면 목록에 없다고 지워졌다고 볼 수 없다
+            if complete {
+                let missing = state.stored
+                    .iter()
+                    .filter(|no| !seen.contains(no) && !state.deleted.contains(no))
+                    .copied()
+                    .collect::<Vec<_>>();
+
+                for no in missing {
+                    state.deleted.insert(no);
+                    deletes.push(no);
+                }
+            }
+        }
+
+        for comment in inserts {
+            self.inner.comment.insert((article, comment).into())
+                .await
+                .context("fail to insert comment")?;
+        }
+
+        for no in deletes {
+            self.inner.deleted.insert((article, no).into())
+                .await
+                .context("fail to insert comment deleted")?;
+        }
+
+        Ok(())
+    }
+
+    async fn load(&self, gallery: &Gallery, article_id: u64) -> anyhow::Result<Known> {
+        let stored = self.inner.client
+            .query("SELECT no, deleted FROM ? WHERE gallery = ? AND article_id = ?")
+            .bind(Identifier(Comment::table_name()))
+            .bind(gallery.to_string())
+            .bind(article_id)
+            .fetch_all::<(u64, bool)>()
+            .await
+            .context("fail to fetch stored comments")?;
+
+        let deleted = self.inner.client
+            .query("SELECT no FROM ? WHERE gallery = ? AND article_id = ?")
+            .bind(Identifier(CommentDeleted::table_name()))
+            .bind(gallery.to_string())
+            .bind(article_id)
+            .fetch_all::<u64>()
+            .await
+            .context("fail to fetch deleted comments")?;
+
+        Ok(Known {
+            stored: stored.iter().map(|(no, _)| *no).collect(),
+            deleted: stored.iter().filter(|(_, deleted)| *deleted).map(|(no, _)| *no).chain(deleted).collect(),
+            touched: Instant::now(),
+        })
+    }
+}
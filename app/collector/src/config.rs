@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::{bail, ensure, Context};
+use clickhouse_entity::batch::BatchOptions;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use crate::article::gallery::Gallery;
+
+// 설정 파일 경로. 기본 경로에 파일이 없으면 기본값과 환경 변수만으로 실행한다
+const CONFIG_ENV: &str = "COLLECTOR_CONFIG";
+const DEFAULT_PATH: &str = "collector.toml";
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub host: String,
+    pub galleries: Vec<String>,
+    pub image_dir: PathBuf,
+    // 첨부 파일 하나의 최대 크기. 넘으면 받다가 멈추고 첨부 오류로 남긴다
+    pub attach_max_bytes: u64,
+    pub proxies: Vec<String>,
+    // 모든 프록시가 쉬는 중일 때 기다리지 않고 직접 요청한다
+    pub proxy_fallback_direct: bool,
+    pub host_rates: BTreeMap<String, HostRate>,
+    pub clickhouse: ClickhouseConfig,
+    pub http: HttpConfig,
+    pub create_article: CreateArticleConfig,
+    pub poll: PollConfig,
+    pub batch: BatchConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClickhouseConfig {
+    pub url: String,
+    pub user: String,
+    pub password: String,
+    pub database: String,
+    pub log_database: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    // 연결을 맺을 때까지, 그리고 응답을 읽는 중 다음 조각이 올 때까지 기다리는 시간
+    pub connect_timeout_secs: u64,
+    pub read_timeout_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CreateArticleConfig {
+    // 본문 수집, 첨부 저장 단계의 worker 수. 목록 확인과 저장 단계는 하나씩 돈다
+    pub view_workers: usize,
+    pub attach_workers: usize,
+    // 단계 사이 큐의 크기. 가득 차면 앞 단계가 기다린다
+    pub queue_size: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PollConfig {
+    pub create_article_secs: u64,
+    pub backfill_secs: u64,
+    pub snapshot_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchConfig {
+    pub max_rows: usize,
+    pub flush_interval_secs: u64,
+}
+
+// 호스트별 초당 요청 수와 버스트
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostRate {
+    pub per_sec: f64,
+    pub burst: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: "https://gall.dcinside.com".to_string(),
+            galleries: vec!["baseball_new13".to_string()],
+            image_dir: PathBuf::from("./data/attach"),
+            attach_max_bytes: 100 * 1024 * 1024,
+            proxies: Vec::new(),
+            proxy_fallback_direct: false,
+            host_rates: BTreeMap::new(),
+            clickhouse: ClickhouseConfig::default(),
+            http: HttpConfig::default(),
+            create_article: CreateArticleConfig::default(),
+            poll: PollConfig::default(),
+            batch: BatchConfig::default(),
+        }
+    }
+}
+
+impl Default for ClickhouseConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:8123".to_string(),
+            user: "admin".to_string(),
+            password: String::new(),
+            database: "baseball".to_string(),
+            log_database: "application_log".to_string(),
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for CreateArticleConfig {
+    fn default() -> Self {
+        Self {
+            view_workers: 4,
+            attach_workers: 4,
+            queue_size: 64,
+        }
+    }
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            create_article_secs: 1,
+            backfill_secs: 60,
+            snapshot_secs: 600,
+        }
+    }
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        let options = BatchOptions::default();
+
+        Self {
+            max_rows: options.max_rows,
+            flush_interval_secs: options.flush_interval.as_secs(),
+        }
+    }
+}
+
+// "gall.dcinside.com=2:4,dcimg8.dcinside.co.kr=5:10"
+fn parse_host_rates(v: &str) -> anyhow::Result<BTreeMap<String, HostRate>> {
+    let mut result = BTreeMap::new();
+
+    for entry in v.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let Some((host, rate)) = entry.split_once('=') else { bail!("invalid host rate {}", entry); };
+        let Some((per_sec, burst)) = rate.split_once(':') else { bail!("invalid host rate {}", entry); };
+
+        result.insert(host.trim().to_string(), HostRate {
+            per_sec: per_sec.trim().parse().context(format!("invalid host rate {}", entry))?,
+            burst: burst.trim().parse().context(format!("invalid host rate {}", entry))?,
+        });
+    }
+
+    Ok(result)
+}
+
+fn parse_list(v: &str) -> Vec<String> {
+    v.split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+fn is_identifier(v: &str) -> bool {
+    !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn env<T: FromStr>(name: &str, target: &mut T) -> anyhow::Result<()>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    if let Ok(v) = std::env::var(name) {
+        *target = v.trim().parse().context(format!("invalid {}", name))?;
+    }
+
+    Ok(())
+}
+
+impl Config {
+    fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let explicit = path
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::var_os(CONFIG_ENV).map(PathBuf::from));
+        let path = explicit.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_PATH));
+
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(v) => toml::from_str::<Config>(&v).context(format!("invalid config {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && explicit.is_none() => Config::default(),
+            Err(e) => return Err(e).context(format!("fail to read config {}", path.display())),
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // 파일보다 환경 변수가 우선한다
+    fn apply_env(&mut self) -> anyhow::Result<()> {
+        env("DCINSIDE_HOST", &mut self.host)?;
+        env("IMAGE_DIR", &mut self.image_dir)?;
+        env("ATTACH_MAX_BYTES", &mut self.attach_max_bytes)?;
+        env("PROXY_FALLBACK_DIRECT", &mut self.proxy_fallback_direct)?;
+        env("CLICKHOUSE_URL", &mut self.clickhouse.url)?;
+        env("CLICKHOUSE_USER", &mut self.clickhouse.user)?;
+        env("CLICKHOUSE_PASSWORD", &mut self.clickhouse.password)?;
+        env("CLICKHOUSE_DATABASE", &mut self.clickhouse.database)?;
+        env("CLICKHOUSE_LOG_DATABASE", &mut self.clickhouse.log_database)?;
+        env("HTTP_CONNECT_TIMEOUT_SECS", &mut self.http.connect_timeout_secs)?;
+        env("HTTP_READ_TIMEOUT_SECS", &mut self.http.read_timeout_secs)?;
+        env("CREATE_ARTICLE_VIEW_WORKERS", &mut self.create_article.view_workers)?;
+        env("CREATE_ARTICLE_ATTACH_WORKERS", &mut self.create_article.attach_workers)?;
+        env("CREATE_ARTICLE_QUEUE_SIZE", &mut self.create_article.queue_size)?;
+        env("CREATE_ARTICLE_POLL_SECS", &mut self.poll.create_article_secs)?;
+        env("BACKFILL_POLL_SECS", &mut self.poll.backfill_secs)?;
+        env("SNAPSHOT_POLL_SECS", &mut self.poll.snapshot_secs)?;
+        env("BATCH_MAX_ROWS", &mut self.batch.max_rows)?;
+        env("BATCH_FLUSH_INTERVAL_SECS", &mut self.batch.flush_interval_secs)?;
+
+        if let Ok(v) = std::env::var("GALLERIES") {
+            self.galleries = parse_list(&v);
+        }
+        if let Ok(v) = std::env::var("PROXIES") {
+            self.proxies = parse_list(&v);
+        }
+        if let Ok(v) = std::env::var("HOST_RATES") {
+            self.host_rates = parse_host_rates(&v)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(self.host.starts_with("http://") || self.host.starts_with("https://"), "host must be an http(s) url: {}", self.host);
+        ensure!(!self.host.ends_with('/'), "host must not end with '/': {}", self.host);
+        ensure!(!self.galleries.is_empty(), "galleries is empty");
+        ensure!(self.attach_max_bytes > 0, "attach_max_bytes must be positive");
+        for gallery in self.galleries.iter() {
+            gallery.parse::<Gallery>().context(format!("invalid gallery {}", gallery))?;
+        }
+        for proxy in self.proxies.iter() {
+            reqwest::Proxy::all(proxy.as_str()).context(format!("invalid proxy {}", proxy))?;
+        }
+        for (host, rate) in self.host_rates.iter() {
+            ensure!(rate.per_sec > 0.0 && rate.burst >= 1.0, "invalid host rate for {}: {:?}", host, rate);
+        }
+
+        let clickhouse = &self.clickhouse;
+        ensure!(clickhouse.url.starts_with("http://") || clickhouse.url.starts_with("https://"), "clickhouse.url must be an http(s) url: {}", clickhouse.url);
+        ensure!(!clickhouse.user.is_empty(), "clickhouse.user is empty");
+        // migrate 에서 SQL 에 그대로 넣는다
+        ensure!(is_identifier(&clickhouse.database), "invalid clickhouse.database: {}", clickhouse.database);
+        ensure!(is_identifier(&clickhouse.log_database), "invalid clickhouse.log_database: {}", clickhouse.log_database);
+
+        ensure!(self.http.connect_timeout_secs > 0, "http.connect_timeout_secs must be positive");
+        ensure!(self.http.read_timeout_secs > 0, "http.read_timeout_secs must be positive");
+        ensure!(self.create_article.view_workers > 0, "create_article.view_workers must be positive");
+        ensure!(self.create_article.attach_workers > 0, "create_article.attach_workers must be positive");
+        ensure!(self.create_article.queue_size > 0, "create_article.queue_size must be positive");
+        ensure!(self.poll.create_article_secs > 0, "poll.create_article_secs must be positive");
+        ensure!(self.poll.backfill_secs > 0, "poll.backfill_secs must be positive");
+        ensure!(self.poll.snapshot_secs > 0, "poll.snapshot_secs must be positive");
+        ensure!(self.batch.max_rows > 0, "batch.max_rows must be positive");
+        ensure!(self.batch.flush_interval_secs > 0, "batch.flush_interval_secs must be positive");
+
+        Ok(())
+    }
+
+    pub fn clickhouse_client(&self, database: &str) -> clickhouse::Client {
+        clickhouse::Client::default()
+            .with_url(self.clickhouse.url.as_str())
+            .with_user(self.clickhouse.user.as_str())
+            .with_password(self.clickhouse.password.as_str())
+            .with_database(database)
+    }
+
+    pub fn batch_options(&self) -> BatchOptions {
+        BatchOptions {
+            max_rows: self.batch.max_rows,
+            flush_interval: Duration::from_secs(self.batch.flush_interval_secs),
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs)
+    }
+
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.read_timeout_secs)
+    }
+}
+
+impl PollConfig {
+    pub fn create_article(&self) -> Duration {
+        Duration::from_secs(self.create_article_secs)
+    }
+
+    pub fn backfill(&self) -> Duration {
+        Duration::from_secs(self.backfill_secs)
+    }
+
+    pub fn snapshot(&self) -> Duration {
+        Duration::from_secs(self.snapshot_secs)
+    }
+}
+
+// 부팅할 때 한 번 읽고 검증한다. path 가 없으면 COLLECTOR_CONFIG, 그것도 없으면 ./collector.toml
+pub fn init(path: Option<&Path>) -> anyhow::Result<&'static Config> {
+    let config = Config::load(path)?;
+
+    if CONFIG.set(config).is_err() {
+        bail!("config already initialized");
+    }
+
+    Ok(get())
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get().expect("config is not initialized")
+}
@@ -4,32 +4,88 @@ use entity::Entity;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
-#[entity(table = "logs")]
+#[entity(table = "article")]
 pub struct Article {
     #[serde(with = "clickhouse::serde::uuid")]
     uid: uuid::Uuid,
+    gallery: String,
     id: u64,
     #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
     timestamp: DateTime<Utc>,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
+    created_at: DateTime<Utc>,
     author: String,
+    author_uid: Option<String>,
+    author_ip: Option<String>,
+    author_kind: String,
     subject: String,
     content: String,
+    content_html: String,
+    content_markdown: String,
     attach_origin_src: Vec<String>,
     attach_copied_path: Vec<String>,
+    attach_hash: Vec<String>,
+    attach_error_src: Vec<String>,
+    attach_error: Vec<String>,
+    views: u64,
+    upvotes: u64,
+    downvotes: u64,
+    comments: u64,
 }
 
-impl From<(article::Article, Vec<crate::article::attach::ArticleFile>)> for Article {
-    fn from(v: (article::Article, Vec<crate::article::attach::ArticleFile>)) -> Self {
+impl From<(article::Article, crate::article::attach::SavedAttach)> for Article {
+    fn from(v: (article::Article, crate::article::attach::SavedAttach)) -> Self {
         let (article, attach) = v;
         Self {
             uid: uuid::Uuid::now_v7(),
+            gallery: article.gallery,
             id: article.id,
             timestamp: article.timestamp,
+            created_at: article.created_at,
             author: article.author,
+            author_uid: article.author_uid,
+            author_ip: article.author_ip,
+            author_kind: article.author_kind.as_str().to_string(),
             subject: article.subject,
             content: article.content,
-            attach_origin_src: attach.iter().map(|o| o.origin_src.clone()).collect(),
-            attach_copied_path: attach.iter().map(|o| o.copied_path.to_str().unwrap().to_string()).collect(),
+            content_html: article.content_html,
+            content_markdown: article.content_markdown,
+            attach_origin_src: attach.files.iter().map(|o| o.origin_src.clone()).collect(),
+            attach_copied_path: attach.files.iter().map(|o| o.copied_path.to_str().unwrap().to_string()).collect(),
+            attach_hash: attach.files.iter().map(|o| o.hash.clone()).collect(),
+            attach_error_src: attach.errors.iter().map(|o| o.origin_src.clone()).collect(),
+            attach_error: attach.errors.iter().map(|o| o.reason.clone()).collect(),
+            views: article.counter.views,
+            upvotes: article.counter.upvotes,
+            downvotes: article.counter.downvotes,
+            comments: article.counter.comments,
         }
     }
-}
\ No newline at end of file
+}
+// 첨부 내용(해시)마다 처음 저장한 시각, 크기, 시그니처로 판별한 MIME
+// 본 때마다 넣고 병합할 때 가장 이른 first_seen 만 남는다
+#[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
+#[entity(table = "attachment")]
+pub struct Attachment {
+    #[serde(with = "clickhouse::serde::uuid")]
+    uid: uuid::Uuid,
+    hash: String,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
+    first_seen: DateTime<Utc>,
+    size: u64,
+    mime: String,
+    known_type: bool,
+}
+
+impl From<&crate::article::attach::ArticleFile> for Attachment {
+    fn from(v: &crate::article::attach::ArticleFile) -> Self {
+        Self {
+            uid: uuid::Uuid::now_v7(),
+            hash: v.hash.clone(),
+            first_seen: Utc::now(),
+            size: v.size,
+            mime: v.mime.clone(),
+            known_type: v.known_type,
+        }
+    }
+}
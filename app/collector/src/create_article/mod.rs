@@ -1,4 +1,8 @@
-mod entity;
+pub(crate) mod entity;
+mod pipeline;
+mod seen;
 mod spawn;
+mod store;
 
-pub use spawn::run;
\ No newline at end of file
+pub use spawn::{run, store};
+pub use store::ArticleStore;
@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::bail;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+
+const METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+// 단계 입력 큐의 적재량, 처리 수, 앞 단계가 큐가 가득 차서 기다린 시간
+pub struct Metrics {
+    stage: &'static str,
+    capacity: usize,
+    queued: AtomicI64,
+    processed: AtomicU64,
+    blocked_micros: AtomicU64,
+}
+
+impl Metrics {
+    fn report(&self) {
+        tracing::info!(
+            stage = self.stage,
+            capacity = self.capacity,
+            queued = self.queued.load(Ordering::Relaxed),
+            processed = self.processed.swap(0, Ordering::Relaxed),
+            blocked_ms = self.blocked_micros.swap(0, Ordering::Relaxed) / 1000,
+            "pipeline stage"
+        );
+    }
+}
+
+pub struct Sender<T> {
+    sender: mpsc::Sender<T>,
+    metrics: Arc<Metrics>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    // 큐가 가득 차 있으면 빈자리가 날 때까지 기다린다. 기다린 시간은 가득 찼을 때만 잰다
+    pub async fn send(&self, v: T) -> anyhow::Result<()> {
+        // 받는 쪽이 먼저 꺼내 queued 가 음수가 되지 않도록 넣기 전에 센다. 실패하거나 취소되면 되돌린다
+        self.metrics.queued.fetch_add(1, Ordering::Relaxed);
+        let mut queued = Queued { metrics: &self.metrics, sent: false };
+
+        let v = match self.sender.try_send(v) {
+            Ok(()) => {
+                queued.sent = true;
+                return Ok(());
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => bail!("{} queue closed", self.metrics.stage),
+            Err(mpsc::error::TrySendError::Full(v)) => v,
+        };
+
+        let started = Instant::now();
+        if self.sender.send(v).await.is_err() {
+            bail!("{} queue closed", self.metrics.stage);
+        }
+        queued.sent = true;
+
+        self.metrics.blocked_micros.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
+struct Queued<'a> {
+    metrics: &'a Metrics,
+    sent: bool,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        if !self.sent {
+            self.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// 같은 단계의 worker 들이 나눠 읽는다
+pub struct Receiver<T> {
+    receiver: Arc<Mutex<mpsc::Receiver<T>>>,
+    metrics: Arc<Metrics>,
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    // 모든 Sender 가 drop 되고 큐가 비면 None
+    pub async fn recv(&self) -> Option<T> {
+        let v = self.receiver.lock().await.recv().await?;
+
+        self.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+        self.metrics.processed.fetch_add(1, Ordering::Relaxed);
+
+        Some(v)
+    }
+}
+
+pub fn queue<T>(stage: &'static str, capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = mpsc::channel::<T>(capacity);
+    let metrics = Arc::new(Metrics {
+        stage,
+        capacity,
+        queued: AtomicI64::new(0),
+        processed: AtomicU64::new(0),
+        blocked_micros: AtomicU64::new(0),
+    });
+
+    (
+        Sender { sender, metrics: metrics.clone() },
+        Receiver { receiver: Arc::new(Mutex::new(receiver)), metrics },
+    )
+}
+
+pub fn metrics<T>(receiver: &Receiver<T>) -> Arc<Metrics> {
+    receiver.metrics.clone()
+}
+
+// 단계별 지표를 METRICS_INTERVAL 마다 로그로 남긴다
+pub async fn report(metrics: Vec<Arc<Metrics>>) {
+    let mut interval = tokio::time::interval(METRICS_INTERVAL);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        for v in metrics.iter() {
+            v.report();
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeSet;
+use anyhow::Context;
+use clickhouse::sql::Identifier;
+use entity::Entity;
+use crate::article::gallery::Gallery;
+use crate::create_article::entity::Article;
+
+// 이미 수집한 게시글 번호. 번호가 증가하므로 가장 작은 번호부터 밀어낸다
+pub struct SeenIds {
+    ids: BTreeSet<u64>,
+    capacity: usize,
+}
+
+impl SeenIds {
+    pub async fn warm(client: &clickhouse::Client, gallery: &Gallery, capacity: usize) -> anyhow::Result<Self> {
+        let ids = client
+            .query("SELECT DISTINCT id FROM ? WHERE gallery = ? ORDER BY id DESC LIMIT ?")
+            .bind(Identifier(Article::table_name()))
+            .bind(gallery.to_string())
+            .bind(capacity as u64)
+            .fetch_all::<u64>()
+            .await
+            .context("fail to fetch collected article ids")?;
+
+        let mut seen = Self {
+            ids: BTreeSet::new(),
+            capacity,
+        };
+
+        for id in ids {
+            seen.insert(id);
+        }
+
+        Ok(seen)
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        if self.ids.contains(&id) {
+            return true;
+        }
+
+        // 윈도우가 가득 찼다면 윈도우보다 오래된 번호는 이미 지나간 것으로 본다
+        match self.ids.first() {
+            Some(first) => self.ids.len() >= self.capacity && id < *first,
+            None => false,
+        }
+    }
+
+    pub fn insert(&mut self, id: u64) {
+        self.ids.insert(id);
+
+        while self.ids.len() > self.capacity {
+            self.ids.pop_first();
+        }
+    }
+}
@@ -1,22 +1,164 @@
+use std::collections::HashSet;
 use std::time::Duration;
+use anyhow::Context;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 use tracing::instrument;
-use clickhouse_entity::batch;
 use util::report;
 use util::shutdown::Shutdown;
-use crate::article::article::get_article;
+use crate::article::gallery::Gallery;
+use crate::article::article::{get_article, Article, ArticleResult};
+use crate::article::attach::{save_files, SavedAttach};
+use crate::article::error::ArticleError;
 use crate::article::list::{collect_list};
-use crate::create_article;
+use crate::comment::CommentStore;
+use crate::config;
+use crate::create_article::pipeline;
+use crate::create_article::store::ArticleStore;
+use crate::http::limit::Priority;
+use crate::http::session::Session;
+use crate::create_article::seen::SeenIds;
 
-#[instrument(skip(http_client, clickhouse_client))]
-pub async fn run(http_client: reqwest::Client, clickhouse_client: clickhouse::Client) -> anyhow::Result<impl Shutdown> {
-    let mut interval = tokio::time::interval(Duration::from_secs(1));
+const SEEN_CAPACITY: usize = 10000;
+// 종료 신호를 받은 뒤 이미 받은 글을 처리하는 최대 시간. 넘으면 남은 글은 버리고 끝낸다
+// COOL_DOWN, 프록시 대기 중이면 기다리지 않고 끊는다. 저장 단계는 첨부 단계가 넘긴 글까지 받을 수 있게 더 기다린다
+const ATTACH_DRAIN: Duration = Duration::from_secs(30);
+const PERSIST_DRAIN: Duration = Duration::from_secs(60);
 
-    let batch: batch::Batch<create_article::entity::Article> = batch::Batch::run(clickhouse_client).await;
+// 목록 확인 → 본문 수집 → 첨부/댓글 저장 → 저장 순서로 단계마다 따로 돌고, 단계 사이는 크기가 정해진 큐로 잇는다
+// 처리를 마친 번호는 (번호, 다시 시도할 필요가 없으면 true) 로 목록 단계에 알린다
+type Done = mpsc::UnboundedSender<(u64, bool)>;
+
+pub struct CreateArticle {
+    close_sender: watch::Sender<bool>,
+    discover: JoinHandle<()>,
+    view: Vec<JoinHandle<()>>,
+    attach: Vec<JoinHandle<()>>,
+    persist: JoinHandle<()>,
+    metrics: JoinHandle<()>,
+}
+
+// 목록 단계와 본문 단계는 하던 요청도 끊고 멈추고, 첨부/저장 단계는 이미 받은 글을 *_DRAIN 동안 처리한 뒤 끝난다
+#[async_trait::async_trait]
+impl Shutdown for CreateArticle {
+    async fn shutdown(self) {
+        if self.close_sender.send(true).is_err() {
+            println!("create article send close fail");
+        }
+
+        let stages = [
+            ("discover", vec![self.discover]),
+            ("view", self.view),
+            ("attach", self.attach),
+            ("persist", vec![self.persist]),
+        ];
+
+        for (stage, handles) in stages {
+            for handle in handles {
+                if let Some(err) = handle.await.err() {
+                    println!("create article {} wait close fail {}", stage, err);
+                }
+            }
+        }
+
+        self.metrics.abort();
+    }
+}
+
+#[instrument(skip(http_client, clickhouse_client, comment_store))]
+pub async fn run(
+    http_client: Session,
+    clickhouse_client: clickhouse::Client,
+    comment_store: CommentStore,
+    gallery: Gallery,
+) -> anyhow::Result<impl Shutdown> {
+    let options = &config::get().create_article;
+    let seen = SeenIds::warm(&clickhouse_client, &gallery, SEEN_CAPACITY).await?;
+    let store = ArticleStore::run(clickhouse_client, config::get().batch_options()).await;
+
+    let (close_sender, close_receiver) = watch::channel(false);
+    let (done_sender, done_receiver) = mpsc::unbounded_channel::<(u64, bool)>();
+    let (view_sender, view_receiver) = pipeline::queue::<u64>("view", options.queue_size);
+    let (attach_sender, attach_receiver) = pipeline::queue::<Article>("attach", options.queue_size);
+    let (persist_sender, persist_receiver) = pipeline::queue::<(Article, SavedAttach)>("persist", options.queue_size);
+
+    let metrics = tokio::spawn(pipeline::report(vec![
+        pipeline::metrics(&view_receiver),
+        pipeline::metrics(&attach_receiver),
+        pipeline::metrics(&persist_receiver),
+    ]));
+
+    let discover = tokio::spawn(Priority::High.scope(discover(
+        http_client.clone(),
+        gallery.clone(),
+        seen,
+        view_sender,
+        done_receiver,
+        close_receiver.clone(),
+    )));
+
+    let view = (0..options.view_workers)
+        .map(|_| tokio::spawn(Priority::High.scope(view(
+            http_client.clone(),
+            gallery.clone(),
+            view_receiver.clone(),
+            attach_sender.clone(),
+            done_sender.clone(),
+            close_receiver.clone(),
+        ))))
+        .collect();
+    drop(attach_sender);
+
+    let attach = (0..options.attach_workers)
+        .map(|_| tokio::spawn(Priority::High.scope(attach(
+            http_client.clone(),
+            gallery.clone(),
+            comment_store.clone(),
+            attach_receiver.clone(),
+            persist_sender.clone(),
+            done_sender.clone(),
+            close_receiver.clone(),
+        ))))
+        .collect();
+    drop(persist_sender);
+
+    let persist = tokio::spawn(persist(store, persist_receiver, done_sender, close_receiver));
+
+    Ok(CreateArticle {
+        close_sender,
+        discover,
+        view,
+        attach,
+        persist,
+        metrics,
+    })
+}
+
+async fn discover(
+    http_client: Session,
+    gallery: Gallery,
+    mut seen: SeenIds,
+    view_sender: pipeline::Sender<u64>,
+    mut done_receiver: mpsc::UnboundedReceiver<(u64, bool)>,
+    mut close_receiver: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(config::get().poll.create_article());
+    let mut in_flight = HashSet::<u64>::new();
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = close_receiver.changed() => break,
+        }
 
-        let headers = match collect_list(http_client.clone()).await {
+        while let Ok((id, done)) = done_receiver.try_recv() {
+            in_flight.remove(&id);
+            if done {
+                seen.insert(id);
+            }
+        }
+
+        let headers = match collect_list(http_client.clone(), &gallery, 1).await {
             Ok(v) => v,
             Err(e) => {
                 report!(e, "Failed to get headers");
@@ -25,16 +167,195 @@ pub async fn run(http_client: reqwest::Client, clickhouse_client: clickhouse::Cl
         };
 
         for header in headers {
-            interval.tick().await;
-            let article = match get_article(http_client.clone(), header.id).await {
-                Ok(v) => v,
-                Err(e) => {
-                    report!(e, "Failed to get article");
-                    continue;
-                }
-            };
+            if seen.contains(header.id) || !in_flight.insert(header.id) {
+                continue;
+            }
+
+            // 본문 단계가 밀려 있으면 여기서 기다린다
+            tokio::select! {
+                result = view_sender.send(header.id) => if result.is_err() { return; },
+                _ = close_receiver.changed() => return,
+            }
+        }
+    }
+}
+
+async fn view(
+    http_client: Session,
+    gallery: Gallery,
+    view_receiver: pipeline::Receiver<u64>,
+    attach_sender: pipeline::Sender<Article>,
+    done: Done,
+    mut close_receiver: watch::Receiver<bool>,
+) {
+    loop {
+        let id = tokio::select! {
+            v = view_receiver.recv() => match v {
+                Some(v) => v,
+                None => break,
+            },
+            _ = close_receiver.changed() => break,
+        };
+
+        // COOL_DOWN 이나 프록시가 풀리기를 기다리는 중일 수 있으므로 종료 신호와 겨룬다
+        let result = tokio::select! {
+            v = get_article(http_client.clone(), &gallery, id) => v,
+            _ = close_receiver.changed() => break,
+        };
+
+        let article = match result {
+            Ok(ArticleResult::Article(v)) => v,
+            Ok(ArticleResult::DeletedArticle) => {
+                let _ = done.send((id, true));
+                continue;
+            }
+            // 이후 요청은 COOL_DOWN 이 풀릴 때까지 대기한다
+            Err(ArticleError::Blocked) => {
+                let _ = done.send((id, false));
+                continue;
+            }
+            Err(ArticleError::AdultGate) => {
+                tracing::info!(id, "skip adult article");
+                let _ = done.send((id, true));
+                continue;
+            }
+            Err(e) if e.is_transient() => {
+                let e = anyhow::Error::from(e);
+                report!(e, "Failed to get article");
+                let _ = done.send((id, false));
+                continue;
+            }
+            // 레이아웃 변경처럼 다시 시도해도 같은 결과인 경우 알리고 넘어간다
+            Err(e) => {
+                let e = anyhow::Error::from(e);
+                report!(e, "Failed to parse article");
+                let _ = done.send((id, true));
+                continue;
+            }
+        };
+
+        tokio::select! {
+            result = attach_sender.send(article) => if result.is_err() {
+                let _ = done.send((id, false));
+                break;
+            },
+            _ = close_receiver.changed() => break,
         }
     }
+}
+
+async fn attach(
+    http_client: Session,
+    gallery: Gallery,
+    comment_store: CommentStore,
+    attach_receiver: pipeline::Receiver<Article>,
+    persist_sender: pipeline::Sender<(Article, SavedAttach)>,
+    done: Done,
+    close_receiver: watch::Receiver<bool>,
+) {
+    let drain = drain(close_receiver, ATTACH_DRAIN);
+    tokio::pin!(drain);
+
+    loop {
+        let mut article = tokio::select! {
+            v = attach_receiver.recv() => match v {
+                Some(v) => v,
+                None => break,
+            },
+            _ = &mut drain => break,
+        };
+        let id = article.id;
+        let attach_src = std::mem::take(&mut article.attach_src);
+
+        // 끊기면 받던 임시 파일은 attach 의 guard 가 지운다
+        let attach = tokio::select! {
+            v = save_files(http_client.clone(), attach_src, &article.url) => v,
+            _ = &mut drain => break,
+        };
+        let attach = match attach {
+            Ok(v) => v,
+            Err(e) => {
+                // 디스크 쓰기 실패. 받지 못한 첨부는 SavedAttach.errors 로 넘어온다
+                report!(e, "Failed to save attach");
+                let _ = done.send((id, false));
+                continue;
+            }
+        };
+
+        // 처음 저장할 때 달려 있던 댓글. 이후 변화는 snapshot 이 댓글 수가 바뀐 글만 다시 본다
+        let synced = tokio::select! {
+            v = comment_store.sync(http_client.clone(), &gallery, &article) => v,
+            _ = &mut drain => break,
+        };
+        if let Err(e) = synced {
+            report!(e, "Failed to store comments");
+        }
+
+        let sent = tokio::select! {
+            v = persist_sender.send((article, attach)) => v,
+            _ = &mut drain => break,
+        };
+        if sent.is_err() {
+            let _ = done.send((id, false));
+            break;
+        }
+    }
+}
+
+async fn persist(
+    store: ArticleStore,
+    persist_receiver: pipeline::Receiver<(Article, SavedAttach)>,
+    done: Done,
+    close_receiver: watch::Receiver<bool>,
+) {
+    let drain = drain(close_receiver, PERSIST_DRAIN);
+    tokio::pin!(drain);
+
+    loop {
+        let (article, attach) = tokio::select! {
+            v = persist_receiver.recv() => match v {
+                Some(v) => v,
+                None => break,
+            },
+            _ = &mut drain => break,
+        };
+        let id = article.id;
+
+        match store.insert(article, attach).await {
+            Ok(()) => {
+                let _ = done.send((id, true));
+            }
+            Err(e) => {
+                report!(e, "Failed to insert article");
+                let _ = done.send((id, false));
+            }
+        }
+    }
+
+    store.shutdown().await;
+}
+
+// 종료 신호를 받고 after 가 지나면 끝난다
+async fn drain(mut close_receiver: watch::Receiver<bool>, after: Duration) {
+    let _ = close_receiver.wait_for(|v| *v).await;
+    tokio::time::sleep(after).await;
+}
+
+pub async fn store(
+    http_client: Session,
+    gallery: &Gallery,
+    store: &ArticleStore,
+    comment_store: &CommentStore,
+    mut article: Article,
+) -> anyhow::Result<()> {
+    let attach_src = std::mem::take(&mut article.attach_src);
+    let attach = save_files(http_client.clone(), attach_src, &article.url)
+        .await
+        .context("fail to save attach")?;
+
+    if let Err(e) = comment_store.sync(http_client, gallery, &article).await {
+        report!(e, "Failed to store comments");
+    }
 
-    Ok(batch)
+    store.insert(article, attach).await
 }
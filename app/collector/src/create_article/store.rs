@@ -0,0 +1,44 @@
+use anyhow::Context;
+use clickhouse_entity::batch::{Batch, BatchOptions};
+use util::shutdown::Shutdown;
+use crate::article::article::Article;
+use crate::article::attach::SavedAttach;
+use crate::create_article::entity;
+
+// 게시글과 첨부 내용을 함께 저장한다
+pub struct ArticleStore {
+    article: Batch<entity::Article>,
+    attachment: Batch<entity::Attachment>,
+}
+
+#[async_trait::async_trait]
+impl Shutdown for ArticleStore {
+    async fn shutdown(self) {
+        self.article.shutdown().await;
+        self.attachment.shutdown().await;
+    }
+}
+
+impl ArticleStore {
+    pub async fn run(client: clickhouse::Client, options: BatchOptions) -> Self {
+        Self {
+            article: Batch::run(client.clone(), options).await,
+            attachment: Batch::run(client, options).await,
+        }
+    }
+
+    pub async fn insert(&self, article: Article, attach: SavedAttach) -> anyhow::Result<()> {
+        // 파일이 이미 있었는지로 거르지 않는다. 옮긴 뒤 저장 전에 죽으면 행이 영영 빠진다
+        for file in &attach.files {
+            self.attachment.insert(file.into())
+                .await
+                .context("fail to insert attachment")?;
+        }
+
+        self.article.insert((article, attach).into())
+            .await
+            .context("fail to insert article")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// 마지막으로 쉰 뒤 이 시간이 지나면 쉬는 시간을 처음부터 다시 늘린다
+const RESET_AFTER: Duration = Duration::from_secs(3600);
+
+// 막힐 때마다 base 부터 두 배씩 max 까지 늘려 쉬는 기간. 수집기 전체(COOL_DOWN)와 프록시마다 하나씩 쓴다
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    state: Mutex<State>,
+}
+
+struct State {
+    until: Option<Instant>,
+    strikes: u32,
+}
+
+impl Backoff {
+    pub const fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            state: Mutex::new(State { until: None, strikes: 0 }),
+        }
+    }
+
+    // 이미 쉬는 중이면 기간을 늘리지 않고 None. 서버가 Retry-After 로 알려 준 시간이 더 길면 그만큼 쉰다
+    // 새로 쉬게 되면 (누적 횟수, 쉬는 시간)
+    pub fn enter(&self, at_least: Duration) -> Option<(u32, Duration)> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if state.until.is_some_and(|until| until > now) {
+            return None;
+        }
+
+        if state.until.is_some_and(|until| now.duration_since(until) > RESET_AFTER) {
+            state.strikes = 0;
+        }
+
+        let duration = self.base.saturating_mul(2u32.saturating_pow(state.strikes)).max(at_least).min(self.max);
+        state.strikes += 1;
+        state.until = Some(now + duration);
+
+        Some((state.strikes, duration))
+    }
+
+    // 쉬는 중이면 풀리는 시각
+    pub fn until(&self) -> Option<Instant> {
+        self.state.lock().unwrap().until.filter(|until| *until > Instant::now())
+    }
+
+    pub async fn wait(&self) {
+        if let Some(until) = self.until() {
+            tokio::time::sleep_until(until).await;
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+use crate::http::backoff::Backoff;
+
+// 차단/캡차 페이지를 받으면 수집기 전체가 일정 시간 요청을 멈춘다
+pub static COOL_DOWN: CoolDown = CoolDown {
+    backoff: Backoff::new(Duration::from_secs(60), Duration::from_secs(1800)),
+};
+
+pub struct CoolDown {
+    backoff: Backoff,
+}
+
+impl CoolDown {
+    pub fn enter(&self, reason: &str, at_least: Duration) {
+        if let Some((strikes, duration)) = self.backoff.enter(at_least) {
+            tracing::error!(
+                reason,
+                strikes,
+                cool_down_secs = duration.as_secs(),
+                "dcinside block detected, collector cool down"
+            );
+        }
+    }
+
+    pub async fn wait(&self) {
+        self.backoff.wait().await;
+    }
+}
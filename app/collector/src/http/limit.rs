@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use tokio::time::Instant;
+use crate::config;
+
+// 같은 호스트로 가는 모든 요청(목록, 본문, 첨부, 댓글, 삭제 확인)이 하나의 토큰 버킷을 나눠 쓴다
+// 설정의 host_rates 에 없는 호스트는 기본 속도를 쓴다
+pub static LIMITER: Lazy<Limiter> = Lazy::new(|| {
+    let limiter = Limiter::new(Rate::new(2.0, 4.0));
+
+    for (host, rate) in config::get().host_rates.iter() {
+        limiter.set_rate(host, Rate::new(rate.per_sec, rate.burst));
+    }
+
+    limiter
+});
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+// 대기 중인 요청이 있으면 높은 우선순위부터 토큰을 받는다
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low = 0,    // 카운터 스냅샷, 삭제 확인
+    Normal = 1, // 누락 채우기
+    High = 2,   // 새 글 수집
+}
+
+impl Priority {
+    // 이 future 안에서 보내는 요청은 주어진 우선순위로 토큰을 기다린다
+    pub fn scope<F: Future>(self, f: F) -> impl Future<Output = F::Output> {
+        PRIORITY.scope(self, f)
+    }
+
+    fn current() -> Self {
+        PRIORITY.try_with(|v| *v).unwrap_or(Priority::Normal)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rate {
+    per_sec: f64,
+    burst: f64,
+}
+
+impl Rate {
+    pub fn new(per_sec: f64, burst: f64) -> Self {
+        Self {
+            per_sec: per_sec.max(0.01),
+            burst: burst.max(1.0),
+        }
+    }
+}
+
+struct Bucket {
+    rate: Rate,
+    tokens: f64,
+    updated: Instant,
+    waiting: [usize; 3],
+}
+
+impl Bucket {
+    fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            tokens: rate.burst,
+            updated: Instant::now(),
+            waiting: [0; 3],
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate.per_sec).min(self.rate.burst);
+        self.updated = now;
+    }
+
+    fn has_higher_waiting(&self, priority: Priority) -> bool {
+        self.waiting[(priority as usize + 1)..].iter().any(|v| *v > 0)
+    }
+}
+
+pub struct Limiter {
+    default: Rate,
+    rates: Mutex<HashMap<String, Rate>>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl Limiter {
+    pub fn new(default: Rate) -> Self {
+        Self {
+            default,
+            rates: Mutex::new(HashMap::new()),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_rate(&self, host: &str, rate: Rate) {
+        self.rates.lock().unwrap().insert(host.to_string(), rate);
+        self.buckets.lock().unwrap().remove(host);
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn acquire(&self, host: &str) {
+        let priority = Priority::current();
+        let mut queued: Option<Queued> = None;
+
+        loop {
+            let wait = {
+                let rate = self.rates.lock().unwrap().get(host).copied().unwrap_or(self.default);
+                let mut buckets = self.buckets.lock().unwrap();
+                let bucket = buckets
+                    .entry(host.to_string())
+                    .or_insert_with(|| Bucket::new(rate));
+
+                bucket.refill();
+
+                if bucket.tokens >= 1.0 && !bucket.has_higher_waiting(priority) {
+                    bucket.tokens -= 1.0;
+                    None
+                } else {
+                    if queued.is_none() {
+                        bucket.waiting[priority as usize] += 1;
+                        queued = Some(Queued { limiter: self, host, priority });
+                    }
+
+                    let missing = (1.0 - bucket.tokens).max(0.0);
+                    Some(Duration::from_secs_f64(missing / bucket.rate.per_sec).max(Duration::from_millis(10)))
+                }
+            };
+
+            // 대기 표시는 버킷 잠금을 놓은 뒤 Queued 가 drop 되면서 지운다
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+// 토큰을 기다리던 요청이 끝나거나 취소되면 대기 표시를 지운다
+struct Queued<'a> {
+    limiter: &'a Limiter,
+    host: &'a str,
+    priority: Priority,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        if let Some(bucket) = self.limiter.buckets.lock().unwrap().get_mut(self.host) {
+            let waiting = &mut bucket.waiting[self.priority as usize];
+            *waiting = waiting.saturating_sub(1);
+        }
+    }
+}
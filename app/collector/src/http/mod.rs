@@ -0,0 +1,8 @@
+pub mod backoff;
+pub mod cool_down;
+pub mod limit;
+pub mod profile;
+pub mod proxy;
+pub mod random;
+pub mod retry;
+pub mod session;
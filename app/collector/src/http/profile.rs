@@ -0,0 +1,125 @@
+use std::sync::Arc;
+use std::time::Duration;
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, REFERER, USER_AGENT};
+use reqwest::{Client, ClientBuilder, RequestBuilder};
+use crate::config;
+use crate::http::random;
+use crate::http::session::Session;
+
+// 실제 데스크톱 브라우저가 보내는 헤더 묶음
+pub struct Profile {
+    user_agent: &'static str,
+    accept_language: &'static str,
+    sec_ch_ua: Option<&'static str>,
+    platform: Option<&'static str>,
+}
+
+static PROFILES: &[Profile] = &[
+    Profile {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+        accept_language: "ko-KR,ko;q=0.9,en-US;q=0.8,en;q=0.7",
+        sec_ch_ua: Some("\"Google Chrome\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\""),
+        platform: Some("\"Windows\""),
+    },
+    Profile {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+        accept_language: "ko-KR,ko;q=0.9,en-US;q=0.8,en;q=0.7",
+        sec_ch_ua: Some("\"Google Chrome\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\""),
+        platform: Some("\"macOS\""),
+    },
+    Profile {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0",
+        accept_language: "ko,en;q=0.9,en-US;q=0.8",
+        sec_ch_ua: Some("\"Microsoft Edge\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\""),
+        platform: Some("\"Windows\""),
+    },
+    Profile {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:133.0) Gecko/20100101 Firefox/133.0",
+        accept_language: "ko-KR,ko;q=0.8,en-US;q=0.5,en;q=0.3",
+        sec_ch_ua: None,
+        platform: None,
+    },
+];
+
+impl Profile {
+    fn pick() -> &'static Profile {
+        &PROFILES[(random::next_u64() % PROFILES.len() as u64) as usize]
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(self.user_agent));
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static(self.accept_language));
+
+        if let Some(v) = self.sec_ch_ua {
+            headers.insert("sec-ch-ua", HeaderValue::from_static(v));
+            headers.insert("sec-ch-ua-mobile", HeaderValue::from_static("?0"));
+        }
+        if let Some(v) = self.platform {
+            headers.insert("sec-ch-ua-platform", HeaderValue::from_static(v));
+        }
+
+        headers
+    }
+}
+
+// 세션마다 프로필을 새로 고르고, 목록 → 본문 → 이미지 요청 사이에 쿠키를 유지한다
+pub fn session() -> Session {
+    Session::new(Profile::pick().headers())
+}
+
+// 한 세션의 클라이언트들은 프록시만 다르고 헤더와 쿠키 저장소를 나눠 쓴다
+// 멈춘 서버가 worker 를 붙잡고 있지 않도록 연결과 읽기에 타임아웃을 건다
+pub(super) fn client_builder(headers: HeaderMap, jar: Arc<Jar>) -> ClientBuilder {
+    let http = &config::get().http;
+
+    Client::builder()
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(10)
+        .connect_timeout(http.connect_timeout())
+        .read_timeout(http.read_timeout())
+        .default_headers(headers)
+        .cookie_provider(jar)
+}
+
+// 브라우저가 요청 종류별로 붙이는 Accept, Sec-Fetch-* 헤더
+#[derive(Debug, Clone, Copy)]
+pub enum Fetch {
+    Document,
+    Image,
+    Xhr,
+}
+
+pub trait ProfileExt {
+    fn fetch(self, kind: Fetch, referer: Option<&str>) -> Self;
+}
+
+impl ProfileExt for RequestBuilder {
+    fn fetch(self, kind: Fetch, referer: Option<&str>) -> RequestBuilder {
+        let request = match kind {
+            Fetch::Document => self
+                .header(ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8")
+                .header("sec-fetch-dest", "document")
+                .header("sec-fetch-mode", "navigate")
+                .header("sec-fetch-site", if referer.is_some() { "same-origin" } else { "none" })
+                .header("upgrade-insecure-requests", "1"),
+            Fetch::Image => self
+                .header(ACCEPT, "image/avif,image/webp,image/apng,image/*,*/*;q=0.8")
+                .header("sec-fetch-dest", "image")
+                .header("sec-fetch-mode", "no-cors")
+                .header("sec-fetch-site", "cross-site"),
+            Fetch::Xhr => self
+                .header(ACCEPT, "application/json, text/javascript, */*; q=0.01")
+                .header("x-requested-with", "XMLHttpRequest")
+                .header("sec-fetch-dest", "empty")
+                .header("sec-fetch-mode", "cors")
+                .header("sec-fetch-site", "same-origin"),
+        };
+
+        match referer {
+            Some(referer) => request.header(REFERER, referer),
+            None => request,
+        }
+    }
+}
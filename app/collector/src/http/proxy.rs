@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use reqwest::{Proxy, Response};
+use crate::config;
+use crate::http::backoff::Backoff;
+use crate::http::cool_down::COOL_DOWN;
+
+// 설정의 proxies 가 비어 있으면 프록시 없이 각 세션 클라이언트로 직접 요청한다
+pub static PROXY_POOL: Lazy<ProxyPool> = Lazy::new(|| {
+    ProxyPool::new(&config::get().proxies).expect("invalid proxies")
+});
+
+const BASE: Duration = Duration::from_secs(300);
+const MAX: Duration = Duration::from_secs(3600);
+
+// 응답을 받아 온 프록시. 차단 페이지를 받은 쪽에서 해당 프록시를 쉬게 할 때 쓴다
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProxyId(usize);
+
+impl ProxyId {
+    pub fn of(response: &Response) -> Option<ProxyId> {
+        response.extensions().get::<ProxyId>().copied()
+    }
+}
+
+struct Entry {
+    url: String,
+    proxy: Proxy,
+    backoff: Backoff,
+}
+
+// 프록시별로 쉬는 중인지 관리하고, 요청마다 쉬고 있지 않은 프록시를 돌아가며 고른다
+pub struct ProxyPool {
+    entries: Vec<Entry>,
+    next: AtomicUsize,
+}
+
+impl ProxyPool {
+    fn new(urls: &[String]) -> anyhow::Result<Self> {
+        let mut entries = Vec::with_capacity(urls.len());
+
+        for url in urls {
+            entries.push(Entry {
+                url: url.clone(),
+                proxy: Proxy::all(url.as_str())?,
+                backoff: Backoff::new(BASE, MAX),
+            });
+        }
+
+        Ok(Self {
+            entries,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // 쉬고 있지 않은 프록시를 돌아가며 고른다. 모두 쉬는 중이면 None
+    pub(super) fn pick(&self) -> Option<ProxyId> {
+        let len = self.entries.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        (0..len)
+            .map(|i| ProxyId((start + i) % len))
+            .find(|id| !self.benched(*id))
+    }
+
+    fn benched(&self, id: ProxyId) -> bool {
+        self.entries[id.0].backoff.until().is_some()
+    }
+
+    // 가장 먼저 풀리는 프록시가 풀릴 때까지 기다린다
+    pub(super) async fn wait(&self) {
+        let earliest = self.entries
+            .iter()
+            .filter_map(|entry| entry.backoff.until())
+            .min();
+
+        if let Some(until) = earliest {
+            tokio::time::sleep_until(until).await;
+        }
+    }
+
+    pub(super) fn url(&self, id: ProxyId) -> &str {
+        self.entries[id.0].url.as_str()
+    }
+
+    pub(super) fn proxy(&self, id: ProxyId) -> Proxy {
+        self.entries[id.0].proxy.clone()
+    }
+
+    pub(super) fn bench(&self, id: ProxyId, reason: &str, at_least: Duration) {
+        let entry = &self.entries[id.0];
+
+        if let Some((strikes, duration)) = entry.backoff.enter(at_least) {
+            tracing::warn!(
+                proxy = entry.url.as_str(),
+                reason,
+                strikes,
+                bench_secs = duration.as_secs(),
+                "proxy benched"
+            );
+        }
+    }
+}
+
+// 차단 페이지를 받았을 때 프록시를 거쳤다면 그 프록시만 쉬게 하고, 직접 요청이었다면 수집기 전체가 쉰다
+pub fn blocked(proxy: Option<ProxyId>, reason: &str) {
+    blocked_at_least(proxy, reason, Duration::ZERO);
+}
+
+// 429 처럼 서버가 쉴 시간을 알려 준 경우
+pub fn blocked_at_least(proxy: Option<ProxyId>, reason: &str, at_least: Duration) {
+    match proxy {
+        Some(id) => PROXY_POOL.bench(id, reason, at_least),
+        None => COOL_DOWN.enter(reason, at_least),
+    }
+}
@@ -0,0 +1,7 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+// 지터, 프로필 선택처럼 암호학적 품질이 필요 없는 곳에 쓰는 난수
+pub fn next_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
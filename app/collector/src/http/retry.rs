@@ -0,0 +1,140 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tracing::field;
+use tracing::Instrument;
+use crate::http::cool_down::COOL_DOWN;
+use crate::http::limit::LIMITER;
+use crate::http::proxy::{self, ProxyId, PROXY_POOL};
+use crate::http::random;
+use crate::http::session::Session;
+
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+#[async_trait::async_trait]
+pub trait RetryExt {
+    async fn send_with_retry(self) -> reqwest::Result<Response>;
+}
+
+// 일시적인 실패(5xx, 연결 끊김, 타임아웃)는 지수 백오프 + 지터로 MAX_ATTEMPTS 번까지 다시 보낸다
+// 429 는 차단 신호로 보고 다시 보내지 않는다
+pub async fn send(session: &Session, request: RequestBuilder) -> reqwest::Result<Response> {
+    let mut attempt = 1;
+
+    loop {
+        // 본문을 복제할 수 없는 요청은 한 번만 보낸다
+        let Some(current) = request.try_clone().filter(|_| attempt < MAX_ATTEMPTS) else {
+            return send_attempt(session, request, attempt).await;
+        };
+
+        let delay = match send_attempt(session, current, attempt).await {
+            Ok(response) if is_transient_status(response.status()) => {
+                let delay = retry_after(&response)
+                    .map(|v| v.min(MAX_RETRY_AFTER))
+                    .unwrap_or_else(|| backoff(attempt));
+                tracing::warn!(attempt, status = response.status().as_u16(), delay_ms = delay.as_millis() as u64, "http retry");
+                delay
+            }
+            Ok(response) => return Ok(response),
+            Err(e) if is_transient_error(&e) => {
+                let delay = backoff(attempt);
+                tracing::warn!(attempt, error = %e, delay_ms = delay.as_millis() as u64, "http retry");
+                delay
+            }
+            Err(e) => return Err(e),
+        };
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+// 매 시도마다 호스트 단위 토큰을 받은 뒤 보낸다. 요청은 세션이 고른 클라이언트로 보낸다
+async fn send_attempt(session: &Session, request: RequestBuilder, attempt: u32) -> reqwest::Result<Response> {
+    let (_, request) = request.build_split();
+    let request = request?;
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let span = tracing::info_span!(
+        "http_attempt",
+        attempt,
+        host = host.as_str(),
+        proxy = field::Empty,
+    );
+
+    async {
+        COOL_DOWN.wait().await;
+        LIMITER.acquire(&host).await;
+
+        // 앞에서 오래 기다렸을 수 있으므로 프록시는 보내기 직전에 고른다
+        let (proxy, client) = session.client().await;
+        tracing::Span::current().record("proxy", proxy.map(|id| PROXY_POOL.url(id)).unwrap_or("direct"));
+
+        // 차단 페이지 판단은 응답을 읽는 쪽에서 proxy::blocked 로 한다
+        let response = match proxy {
+            None => client.execute(request).await?,
+            Some(id) => match client.execute(request).await {
+                Ok(mut response) => {
+                    response.extensions_mut().insert(id);
+                    response
+                }
+                Err(e) if e.is_timeout() => {
+                    PROXY_POOL.bench(id, "timeout", Duration::ZERO);
+                    return Err(e);
+                }
+                Err(e) if e.is_connect() => {
+                    PROXY_POOL.bench(id, "connect", Duration::ZERO);
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            },
+        };
+
+        // 한도를 넘긴 채로 다시 보내면 차단만 길어진다. 수집기(프록시를 거쳤다면 그 프록시)가 Retry-After 이상 쉰다
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            proxy::blocked_at_least(ProxyId::of(&response), "429", retry_after(&response).unwrap_or_default());
+        }
+
+        Ok(response)
+    }
+    .instrument(span)
+    .await
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error()
+}
+
+fn is_transient_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.is_request() || e.is_body()
+}
+
+// 초 단위 또는 HTTP-date 형식
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    let delay = match value.parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => (DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    };
+
+    Some(delay)
+}
+
+// full jitter: [0, min(MAX_DELAY, BASE_DELAY * 2^(attempt - 1))]
+fn backoff(attempt: u32) -> Duration {
+    let ceiling = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(MAX_DELAY);
+
+    ceiling.mul_f64((random::next_u64() % 1000) as f64 / 1000.0)
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use reqwest::cookie::Jar;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Serialize;
+use crate::config;
+use crate::http::profile::{self, Fetch, ProfileExt};
+use crate::http::proxy::{ProxyId, PROXY_POOL};
+use crate::http::retry::{self, RetryExt};
+
+// 갤러리마다 하나씩 쓰는 브라우저 세션. 목록 → 본문 → 이미지 요청이 같은 프로필과 쿠키로 나간다
+// 프록시는 요청마다 돌아가며 고르고, 프록시별 클라이언트는 세션의 쿠키 저장소를 함께 쓴다
+#[derive(Clone)]
+pub struct Session {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    headers: HeaderMap,
+    jar: Arc<Jar>,
+    direct: Client,
+    proxies: Mutex<HashMap<ProxyId, Client>>,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session").finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    pub(super) fn new(headers: HeaderMap) -> Self {
+        let jar = Arc::new(Jar::default());
+        let direct = profile::client_builder(headers.clone(), jar.clone()).build().unwrap();
+
+        Self {
+            inner: Arc::new(Inner {
+                headers,
+                jar,
+                direct,
+                proxies: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Request {
+        Request {
+            session: self.clone(),
+            builder: self.inner.direct.get(url),
+        }
+    }
+
+    pub fn post(&self, url: &str) -> Request {
+        Request {
+            session: self.clone(),
+            builder: self.inner.direct.post(url),
+        }
+    }
+
+    // 요청을 보내기 직전에 부른다. 쓸 수 있는 프록시가 없으면 설정에 따라 직접 보내거나, 하나가 풀릴 때까지 기다린다
+    pub(super) async fn client(&self) -> (Option<ProxyId>, Client) {
+        if PROXY_POOL.is_empty() {
+            return (None, self.inner.direct.clone());
+        }
+
+        loop {
+            match PROXY_POOL.pick() {
+                Some(id) => return (Some(id), self.proxy_client(id)),
+                None if config::get().proxy_fallback_direct => return (None, self.inner.direct.clone()),
+                None => PROXY_POOL.wait().await,
+            }
+        }
+    }
+
+    fn proxy_client(&self, id: ProxyId) -> Client {
+        self.inner.proxies
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| {
+                profile::client_builder(self.inner.headers.clone(), self.inner.jar.clone())
+                    .proxy(PROXY_POOL.proxy(id))
+                    .build()
+                    .unwrap()
+            })
+            .clone()
+    }
+}
+
+// 보낼 클라이언트는 보내는 시점에 세션에서 고른다
+pub struct Request {
+    session: Session,
+    builder: RequestBuilder,
+}
+
+impl Request {
+    pub fn form<T: Serialize + ?Sized>(self, form: &T) -> Self {
+        Self {
+            builder: self.builder.form(form),
+            ..self
+        }
+    }
+}
+
+impl ProfileExt for Request {
+    fn fetch(self, kind: Fetch, referer: Option<&str>) -> Self {
+        Self {
+            builder: self.builder.fetch(kind, referer),
+            ..self
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RetryExt for Request {
+    async fn send_with_retry(self) -> reqwest::Result<Response> {
+        retry::send(&self.session, self.builder).await
+    }
+}
@@ -1,64 +1,196 @@
+use anyhow::{bail, Context};
+use clap::Parser;
 use once_cell::sync::Lazy;
-use reqwest::Client;
-use std::time::Duration;
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{fmt, registry};
 use util::report;
+use crate::article::article::{get_article, ArticleResult};
+use crate::article::gallery::Gallery;
+use crate::cli::{Cli, Command};
+use crate::config::Config;
 use util::shutdown::{Shutdown, ShutdownExtension};
 
 pub mod article;
+mod backfill;
+mod cli;
+mod collector;
+mod comment;
+mod config;
 mod create_article;
-
-pub static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
-    Client::builder()
-        .pool_idle_timeout(Duration::from_secs(90))
-        .pool_max_idle_per_host(10)
-        .build()
-        .unwrap()
-});
+mod http;
+mod migrate;
+mod snapshot;
 
 pub static CLICKHOUSE_CLIENT: Lazy<clickhouse::Client> = Lazy::new(|| {
-    clickhouse::Client::default()
-        .with_url("http://localhost:8123")
-        .with_user("admin")
-        .with_password("password1234")
-        .with_database("baseball")
+    let config = config::get();
+    config.clickhouse_client(&config.clickhouse.database)
 });
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let shutdown = init_log().await;
-    
-    let service = match create_article::run(HTTP_CLIENT.clone(), CLICKHOUSE_CLIENT.clone()).await {
-        Ok(v) => v,
-        Err(err) => {
-            report!(err, "create collect boot fail");
-            return Ok(());
+    let cli = Cli::parse();
+    let config = config::init(cli.config.as_deref())?;
+
+    match cli.command.unwrap_or(Command::Watch) {
+        Command::Watch => {
+            let shutdown = init_log(config, cli.log_level, true).await;
+            watch(config).await;
+            flush_log(shutdown).await;
         }
-    };
+        Command::MonitorDeletes { gallery, hours } => {
+            let shutdown = init_log(config, cli.log_level, true).await;
+            let galleries = match gallery {
+                Some(v) => vec![v],
+                None => config.galleries.clone(),
+            };
+
+            for gallery in galleries {
+                let gallery: Gallery = gallery.parse()?;
+                if let Err(err) = collector::delete::collect_delete(http::profile::session(), &gallery, hours).await {
+                    report!(err, "monitor deletes fail");
+                }
+            }
+
+            flush_log(shutdown).await;
+        }
+        Command::Backfill { gallery, from, to } => {
+            if from > to {
+                bail!("--from {} is greater than --to {}", from, to);
+            }
 
-    service.listen().await;
-    shutdown.listen().await;
+            let shutdown = init_log(config, cli.log_level, true).await;
+            let gallery = pick_gallery(config, gallery)?;
+            let result = backfill::range(http::profile::session(), CLICKHOUSE_CLIENT.clone(), gallery, from, to).await;
+            flush_log(shutdown).await;
+            result?;
+        }
+        Command::Fetch { id, gallery } => {
+            init_log(config, cli.log_level, false).await;
+            let gallery = pick_gallery(config, gallery)?;
+
+            let json = match get_article(http::profile::session(), &gallery, id).await? {
+                ArticleResult::Article(article) => serde_json::to_string_pretty(&article)?,
+                ArticleResult::DeletedArticle => serde_json::to_string_pretty(&serde_json::json!({
+                    "gallery": gallery.to_string(),
+                    "id": id,
+                    "deleted": true,
+                }))?,
+            };
+
+            println!("{}", json);
+        }
+        Command::Migrate => {
+            init_log(config, cli.log_level, false).await;
+            migrate::run(config).await.context("migrate fail")?;
+        }
+    }
 
     Ok(())
 }
 
-async fn init_log() -> Box<impl Shutdown> {
-    let client: clickhouse::Client = clickhouse::Client::default()
-        .with_url("http://localhost:8123")
-        .with_user("admin")
-        .with_password("password1234")
-        .with_database("application_log");
+async fn watch(config: &Config) {
+    let mut services = Vec::new();
+    let mut backfills = Vec::new();
+    let mut snapshots = Vec::new();
+    let mut deletes = Vec::new();
+    let mut comments = Vec::new();
+
+    // 부팅 중 하나라도 실패하면 그때까지 띄운 서비스를 띄운 순서대로 내린다
+    let booted = 'boot: {
+        // 마이너/미니 갤러리는 "minor/id", "mini/id"
+        for gallery in config.galleries.iter() {
+            let gallery: Gallery = match gallery.parse() {
+                Ok(v) => v,
+                Err(err) => {
+                    report!(err, "invalid gallery");
+                    break 'boot false;
+                }
+            };
+
+            // 갤러리마다 브라우저 프로필과 쿠키를 따로 쓴다
+            let http_client = http::profile::session();
+            let comment_store = comment::CommentStore::run(CLICKHOUSE_CLIENT.clone(), config.batch_options()).await;
+            comments.push(comment_store.clone());
+
+            match create_article::run(http_client.clone(), CLICKHOUSE_CLIENT.clone(), comment_store.clone(), gallery.clone()).await {
+                Ok(v) => services.push(v),
+                Err(err) => {
+                    report!(err, "create collect boot fail");
+                    break 'boot false;
+                }
+            };
+
+            match backfill::run(http_client.clone(), CLICKHOUSE_CLIENT.clone(), comment_store.clone(), gallery.clone()).await {
+                Ok(v) => backfills.push(v),
+                Err(err) => {
+                    report!(err, "backfill boot fail");
+                    break 'boot false;
+                }
+            };
+
+            match snapshot::run(http_client.clone(), CLICKHOUSE_CLIENT.clone(), comment_store.clone(), gallery.clone()).await {
+                Ok(v) => snapshots.push(v),
+                Err(err) => {
+                    report!(err, "snapshot boot fail");
+                    break 'boot false;
+                }
+            };
+
+            match collector::delete::run(http_client.clone(), gallery.clone()).await {
+                Ok(v) => deletes.push(v),
+                Err(err) => {
+                    report!(err, "delete monitor boot fail");
+                    break 'boot false;
+                }
+            };
+        }
+
+        true
+    };
+
+    if booted {
+        services.listen().await;
+    } else {
+        services.shutdown().await;
+    }
+    backfills.shutdown().await;
+    snapshots.shutdown().await;
+    deletes.shutdown().await;
+    // 댓글 저장소는 위 서비스들이 나눠 쓰므로 마지막에 내린다
+    comments.shutdown().await;
+}
+
+// 갤러리를 지정하지 않으면 설정의 첫 번째 갤러리
+fn pick_gallery(config: &Config, gallery: Option<String>) -> anyhow::Result<Gallery> {
+    match gallery {
+        Some(v) => v.parse(),
+        None => config.galleries[0].parse(),
+    }
+}
 
-    let (layer, shutdown) = logger::clickhouse::new(client).await;
+// stdout 은 fetch 결과 출력에 쓰므로 로그는 stderr 로 보낸다
+async fn init_log(config: &Config, level: LevelFilter, clickhouse: bool) -> Option<impl Shutdown> {
+    let (layer, shutdown) = if clickhouse {
+        let client = config.clickhouse_client(&config.clickhouse.log_database);
+        let (layer, shutdown) = logger::clickhouse::new(client, config.batch_options()).await;
+        (Some(layer), Some(shutdown))
+    } else {
+        (None, None)
+    };
 
     registry()
-        .with(fmt::layer().pretty())
+        .with(fmt::layer().pretty().with_writer(std::io::stderr))
         .with(layer)
-        .with(LevelFilter::INFO)
+        .with(level)
         .init();
 
-    Box::new(shutdown)
+    shutdown
+}
+
+async fn flush_log(shutdown: Option<impl Shutdown>) {
+    if let Some(shutdown) = shutdown {
+        shutdown.shutdown().await;
+    }
 }
@@ -0,0 +1,87 @@
+use anyhow::Context;
+use crate::config::Config;
+
+// 순서대로 한 번씩만 적용한다. 적용한 버전은 {database}.schema_migrations 에 남긴다
+// 문장 바로 앞에 `-- skip if: <count 쿼리>` 를 두면 그 결과가 0 이 아닐 때 그 문장을 건너뛴다. 중간에 실패해 다시 돌 때 쓴다
+static MIGRATIONS: &[(&str, &str)] = &[
+    ("0001_init", include_str!("../migrations/0001_init.sql")),
+    ("0002_attachment", include_str!("../migrations/0002_attachment.sql")),
+    ("0003_attachment_type", include_str!("../migrations/0003_attachment_type.sql")),
+    ("0004_attach_error", include_str!("../migrations/0004_attach_error.sql")),
+    ("0005_counter_downvotes", include_str!("../migrations/0005_counter_downvotes.sql")),
+    ("0006_comment_deleted", include_str!("../migrations/0006_comment_deleted.sql")),
+    ("0007_attachment_first_seen", include_str!("../migrations/0007_attachment_first_seen.sql")),
+];
+
+const SKIP_IF: &str = "-- skip if:";
+
+pub async fn run(config: &Config) -> anyhow::Result<()> {
+    // 대상 데이터베이스가 아직 없을 수 있으므로 default 로 접속한다
+    let client = config.clickhouse_client("default");
+    let database = config.clickhouse.database.as_str();
+
+    client
+        .query(&format!("CREATE DATABASE IF NOT EXISTS {database}"))
+        .execute()
+        .await
+        .context("fail to create database")?;
+    client
+        .query(&format!("CREATE TABLE IF NOT EXISTS {database}.schema_migrations (version String, applied_at DateTime64(6, 'UTC')) ENGINE = MergeTree ORDER BY version"))
+        .execute()
+        .await
+        .context("fail to create schema_migrations")?;
+
+    let applied = client
+        .query(&format!("SELECT version FROM {database}.schema_migrations"))
+        .fetch_all::<String>()
+        .await
+        .context("fail to fetch applied migrations")?;
+
+    for (version, sql) in MIGRATIONS {
+        if applied.iter().any(|v| v == version) {
+            continue;
+        }
+
+        let sql = sql
+            .replace("{database}", database)
+            .replace("{log_database}", &config.clickhouse.log_database);
+
+        for statement in sql.split(';').map(str::trim).filter(|v| !v.is_empty()) {
+            let statement = match statement.strip_prefix(SKIP_IF) {
+                Some(guarded) => {
+                    let (guard, statement) = guarded.split_once('\n').unwrap_or((guarded, ""));
+                    let matched = client
+                        .query(guard.trim())
+                        .fetch_one::<u64>()
+                        .await
+                        .context(format!("fail to check migration guard {}", version))?;
+
+                    if matched > 0 {
+                        tracing::info!(version, statement = statement.trim(), "migration statement skipped");
+                        continue;
+                    }
+
+                    statement.trim()
+                }
+                None => statement,
+            };
+
+            client
+                .query(statement)
+                .execute()
+                .await
+                .context(format!("fail to apply migration {}", version))?;
+        }
+
+        client
+            .query(&format!("INSERT INTO {database}.schema_migrations (version, applied_at) VALUES (?, now64(6))"))
+            .bind(*version)
+            .execute()
+            .await
+            .context(format!("fail to record migration {}", version))?;
+
+        tracing::info!(version, "migration applied");
+    }
+
+    Ok(())
+}
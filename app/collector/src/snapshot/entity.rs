@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use entity::Entity;
+use serde::{Deserialize, Serialize};
+use crate::article::article;
+use crate::article::gallery::Gallery;
+use crate::article::list::ArticleHeader;
+
+// 시간에 따른 조회수, 추천수, 댓글수 변화를 기록한다. 목록에서 읽은 행은 비추천수를 모른다
+#[derive(Debug, Deserialize, Serialize, clickhouse::Row, Entity)]
+#[entity(table = "article_counter")]
+pub struct ArticleCounter {
+    #[serde(with = "clickhouse::serde::uuid")]
+    uid: uuid::Uuid,
+    gallery: String,
+    id: u64,
+    #[serde(with = "clickhouse::serde::chrono::datetime64::micros")]
+    timestamp: DateTime<Utc>,
+    views: u64,
+    upvotes: u64,
+    downvotes: Option<u64>,
+    comments: u64,
+}
+
+impl From<&article::Article> for ArticleCounter {
+    fn from(v: &article::Article) -> Self {
+        Self {
+            uid: uuid::Uuid::now_v7(),
+            gallery: v.gallery.clone(),
+            id: v.id,
+            timestamp: v.timestamp,
+            views: v.counter.views,
+            upvotes: v.counter.upvotes,
+            downvotes: Some(v.counter.downvotes),
+            comments: v.counter.comments,
+        }
+    }
+}
+
+impl From<(&Gallery, &ArticleHeader)> for ArticleCounter {
+    fn from(v: (&Gallery, &ArticleHeader)) -> Self {
+        let (gallery, header) = v;
+        Self {
+            uid: uuid::Uuid::now_v7(),
+            gallery: gallery.to_string(),
+            id: header.id,
+            timestamp: Utc::now(),
+            views: header.views,
+            upvotes: header.upvotes,
+            downvotes: None,
+            comments: header.comments,
+        }
+    }
+}
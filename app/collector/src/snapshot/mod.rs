@@ -0,0 +1,4 @@
+pub(crate) mod entity;
+mod spawn;
+
+pub use spawn::run;
@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+use anyhow::Context;
+use chrono::Utc;
+use clickhouse::sql::Identifier;
+use entity::Entity;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::instrument;
+use clickhouse_entity::batch;
+use util::report;
+use util::shutdown::Shutdown;
+use crate::article::gallery::Gallery;
+use crate::article::article::{get_article, ArticleResult};
+use crate::article::error::ArticleError;
+use crate::article::list::{collect_until, Cutoff};
+use crate::comment::CommentStore;
+use crate::config;
+use crate::create_article;
+use crate::http::limit::Priority;
+use crate::http::session::Session;
+use crate::snapshot::entity::ArticleCounter;
+
+// 작성 후 TRACK_HOURS 시간 동안 poll.snapshot_secs 마다 목록을 다시 읽어 카운터를 남긴다
+// 본문은 댓글 수가 바뀐 글만 열어 비추천수와 새 댓글을 읽는다
+const TRACK_HOURS: u32 = 24;
+const MAX_PAGE: u32 = 500;
+
+pub struct Snapshot {
+    close_sender: oneshot::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
+
+#[async_trait::async_trait]
+impl Shutdown for Snapshot {
+    async fn shutdown(self) {
+        if self.close_sender.send(()).is_err() {
+            println!("snapshot send close fail");
+        }
+
+        if let Some(err) = self.join_handle.await.err() {
+            println!("snapshot wait close fail {}", err);
+        }
+    }
+}
+
+#[instrument(skip(http_client, clickhouse_client, comment_store))]
+pub async fn run(
+    http_client: Session,
+    clickhouse_client: clickhouse::Client,
+    comment_store: CommentStore,
+    gallery: Gallery,
+) -> anyhow::Result<impl Shutdown> {
+    let comment_counts = last_comment_counts(&clickhouse_client, &gallery).await?;
+    let batch: batch::Batch<ArticleCounter> = batch::Batch::run(clickhouse_client.clone(), config::get().batch_options()).await;
+    let (close_sender, mut close_receiver) = oneshot::channel::<()>();
+
+    let join_handle = tokio::spawn(Priority::Low.scope(async move {
+        tokio::select! {
+            _ = snapshot(http_client, &gallery, &batch, &comment_store, comment_counts) => {}
+            _ = &mut close_receiver => {}
+        }
+
+        batch.shutdown().await;
+    }));
+
+    Ok(Snapshot {
+        close_sender,
+        join_handle,
+    })
+}
+
+async fn snapshot(
+    http_client: Session,
+    gallery: &Gallery,
+    batch: &batch::Batch<ArticleCounter>,
+    comment_store: &CommentStore,
+    mut comment_counts: HashMap<u64, u64>,
+) {
+    let mut poll = tokio::time::interval(config::get().poll.snapshot());
+
+    loop {
+        poll.tick().await;
+
+        let cutoff = Cutoff::Timestamp(Utc::now() - chrono::Duration::hours(TRACK_HOURS as i64));
+        let headers = match collect_until(http_client.clone(), gallery, cutoff, MAX_PAGE).await {
+            Ok(v) => v,
+            Err(e) => {
+                report!(e, "Failed to get headers");
+                continue;
+            }
+        };
+
+        let tracked = headers.iter().map(|h| h.id).collect::<HashSet<_>>();
+        comment_counts.retain(|id, _| tracked.contains(id));
+
+        let mut blocked = false;
+        for header in headers {
+            let changed = comment_counts.get(&header.id).is_none_or(|v| *v != header.comments);
+
+            // 본문을 열지 못하면 목록에서 읽은 값만 남기고, 바뀐 댓글은 다음 주기에 다시 본다
+            let article = if !changed || blocked {
+                None
+            } else {
+                match get_article(http_client.clone(), gallery, header.id).await {
+                    Ok(ArticleResult::Article(v)) => Some(v),
+                    Ok(ArticleResult::DeletedArticle) => continue,
+                    Err(ArticleError::Blocked) => {
+                        blocked = true;
+                        None
+                    }
+                    Err(e) => {
+                        let e = anyhow::Error::from(e);
+                        report!(e, "Failed to get article");
+                        None
+                    }
+                }
+            };
+
+            let counter = match &article {
+                Some(v) => v.into(),
+                None => (gallery, &header).into(),
+            };
+            if let Err(e) = batch.insert(counter).await {
+                report!(e, "Failed to insert article counter");
+            }
+
+            let Some(article) = article else { continue; };
+
+            match comment_store.sync(http_client.clone(), gallery, &article).await {
+                Ok(()) => {
+                    comment_counts.insert(header.id, header.comments);
+                }
+                Err(e) => report!(e, "Failed to store comments"),
+            }
+        }
+    }
+}
+
+// 마지막으로 기록한 댓글 수. 처음 보는 글은 본문을 한 번 연다
+async fn last_comment_counts(client: &clickhouse::Client, gallery: &Gallery) -> anyhow::Result<HashMap<u64, u64>> {
+    let rows = client
+        .query("SELECT id, argMax(comments, timestamp) FROM (SELECT id, comments, timestamp FROM ? WHERE gallery = ? AND timestamp > now() - toIntervalHour(?) UNION ALL SELECT id, comments, timestamp FROM ? WHERE gallery = ? AND created_at > now() - toIntervalHour(?)) GROUP BY id")
+        .bind(Identifier(ArticleCounter::table_name()))
+        .bind(gallery.to_string())
+        .bind(TRACK_HOURS * 2)
+        .bind(Identifier(create_article::entity::Article::table_name()))
+        .bind(gallery.to_string())
+        .bind(TRACK_HOURS * 2)
+        .fetch_all::<(u64, u64)>()
+        .await
+        .context("fail to fetch last comment counts")?;
+
+    Ok(rows.into_iter().collect())
+}
@@ -1,3 +1,4 @@
+use anyhow::bail;
 use crate::WriteClient;
 use clickhouse::insert::Insert;
 use clickhouse::{Client, Row, RowWrite};
@@ -9,6 +10,22 @@ use tokio::task::JoinHandle;
 use tokio::time::timeout;
 use util::shutdown::Shutdown;
 
+// 행 수가 max_rows 에 닿거나 flush_interval 동안 새 행이 없으면 insert 를 끝내고 새로 연다
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+    pub max_rows: usize,
+    pub flush_interval: Duration,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            max_rows: 10000,
+            flush_interval: Duration::from_secs(10),
+        }
+    }
+}
+
 pub struct Batch<T>
 where
     T: Entity + Send + Sync + 'static + RowWrite,
@@ -42,12 +59,12 @@ where
     T: Entity + Send + Sync + 'static + RowWrite,
     T: for<'a> Row<Value<'a> = T>,
 {
-    pub async fn run(client: Client) -> Self {
+    pub async fn run(client: Client, options: BatchOptions) -> Self {
         let (sender, receiver) = mpsc::channel::<BatchMessage<T>>(1024);
 
         let join_handle = {
             tokio::spawn(async move {
-                consume(&client, receiver).await;
+                consume(&client, options, receiver).await;
             })
         };
 
@@ -56,6 +73,14 @@ where
             join_handle,
         }
     }
+
+    pub async fn insert(&self, v: T) -> anyhow::Result<()> {
+        if self.sender.send(BatchMessage::Insert(v)).await.is_err() {
+            bail!("batch channel closed");
+        }
+
+        Ok(())
+    }
 }
 
 pub enum BatchMessage<T> {
@@ -64,7 +89,7 @@ pub enum BatchMessage<T> {
 }
 
 #[tracing::instrument(skip(client))]
-async fn consume<T>(client: &Client, mut receiver: Receiver<BatchMessage<T>>)
+async fn consume<T>(client: &Client, options: BatchOptions, mut receiver: Receiver<BatchMessage<T>>)
 where
     T: Entity + Send + Sync + 'static + RowWrite,
     T: for<'a> Row<Value<'a> = T>,
@@ -79,7 +104,7 @@ where
     let mut counter = 0;
 
     loop {
-        match timeout(Duration::from_secs(10), receiver.recv()).await {
+        match timeout(options.flush_interval, receiver.recv()).await {
             Ok(Some(BatchMessage::Insert(v))) => {
                 counter += 1;
 
@@ -97,7 +122,7 @@ where
             }
         }
 
-        if counter >= 10000 {
+        if counter >= options.max_rows {
             counter = 0;
             insert = switch_table(client, insert).await;
         }
@@ -1,4 +1,3 @@
-use entity_derive;
 pub use entity_derive::Entity;
 
 pub trait Entity: serde::Serialize + for<'de> serde::Deserialize<'de> {
@@ -1,9 +1,9 @@
-use std::time::Duration;
 use clickhouse::Client;
 use clickhouse::insert::Insert;
 use tokio::sync::mpsc::Receiver;
 use tokio::time::timeout;
 use clickhouse_entity::WriteClient;
+use clickhouse_entity::batch::BatchOptions;
 use crate::clickhouse::entity::LogEntity;
 
 async fn switch_table(clickhouse_client: &Client, insert: Insert<LogEntity>) -> Insert<LogEntity> {
@@ -19,7 +19,7 @@ async fn switch_table(clickhouse_client: &Client, insert: Insert<LogEntity>) ->
     }
 }
 
-pub async fn consume(clickhouse_client: &Client, mut receiver: Receiver<LogMessage>) {
+pub async fn consume(clickhouse_client: &Client, options: BatchOptions, mut receiver: Receiver<LogMessage>) {
     let mut insert = match clickhouse_client.insert_table::<LogEntity>().await {
         Ok(r) => r,
         Err(e) => {
@@ -30,7 +30,7 @@ pub async fn consume(clickhouse_client: &Client, mut receiver: Receiver<LogMessa
     let mut counter = 0;
 
     loop {
-        match timeout(Duration::from_secs(10), receiver.recv()).await {
+        match timeout(options.flush_interval, receiver.recv()).await {
             Ok(Some(LogMessage::Entity(row))) => {
                 if let Err(e) = insert.write(&row).await {
                     println!("clickhouse log insert write fail {}", e);
@@ -48,7 +48,7 @@ pub async fn consume(clickhouse_client: &Client, mut receiver: Receiver<LogMessa
 
         counter += 1;
 
-        if counter >= 10000 {
+        if counter >= options.max_rows {
             counter = 0;
             insert = switch_table(clickhouse_client, insert).await;
         }
@@ -59,6 +59,7 @@ pub async fn consume(clickhouse_client: &Client, mut receiver: Receiver<LogMessa
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 pub(crate) enum LogMessage {
     Entity(LogEntity),
     Shutdown,
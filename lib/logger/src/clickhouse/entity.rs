@@ -44,7 +44,7 @@ impl TryFrom<(ClickhouseVisitor, ClickhouseSpan, &Metadata<'_>)> for LogEntity {
         Ok(Self {
             uuid: uuid::Uuid::now_v7(),
             timestamp: time::OffsetDateTime::now_utc(),
-            level: meta.level().clone(),
+            level: *meta.level(),
             user_id: span.user_id,
             extra_keys,
             extra_values,
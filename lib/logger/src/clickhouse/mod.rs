@@ -7,14 +7,15 @@ mod visitor;
 use self::consumer::LogMessage;
 use self::layer::{ClickhouseLayer, ClickhouseLayerShutdown};
 use clickhouse::Client;
+use clickhouse_entity::batch::BatchOptions;
 use tokio::sync::mpsc;
 
-pub async fn new(clickhouse_client: Client) -> (ClickhouseLayer, ClickhouseLayerShutdown) {
+pub async fn new(clickhouse_client: Client, options: BatchOptions) -> (ClickhouseLayer, ClickhouseLayerShutdown) {
     let (sender, receiver) = mpsc::channel::<LogMessage>(1024);
 
     let join_handle = {
         tokio::spawn(async move {
-            consumer::consume(&clickhouse_client, receiver).await;
+            consumer::consume(&clickhouse_client, options, receiver).await;
         })
     };
 
@@ -51,13 +51,13 @@ impl Visit for ClickhouseVisitor {
             "module_path" => self.module_path = Some(v.into()),
             "causes" => self.causes = Some(v.into()),
             "target" => self.target = Some(v.into()),
-            _ => self.record_extra(f, &v),
+            _ => self.record_extra(f, v),
         }
     }
 
     fn record_debug(&mut self, f: &Field, v: &dyn std::fmt::Debug) {
         match f.name() {
-            "message" => self.message = Some(format!("{:?}", v).into()),
+            "message" => self.message = Some(format!("{:?}", v)),
             _ => self.record_extra(f, format!("{:?}", v).as_str()),
         }
     }
@@ -21,3 +21,15 @@ where
         self.shutdown().await;
     }
 }
+
+#[async_trait::async_trait]
+impl<T> Shutdown for Vec<T>
+where
+    T: Shutdown + Send,
+{
+    async fn shutdown(self) {
+        for v in self {
+            v.shutdown().await;
+        }
+    }
+}